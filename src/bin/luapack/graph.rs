@@ -0,0 +1,51 @@
+//! `luapack graph`: print the module graph of a project.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use luapack::{classify_graph, render_graph, GraphExportCtx, GraphFormat};
+
+use crate::project::ProjectArgs;
+
+#[derive(Parser, Debug)]
+pub(crate) struct GraphCmd {
+    #[command(flatten)]
+    project: ProjectArgs,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+
+    /// Write the graph to this file instead of stdout
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+pub(crate) fn run(cmd: GraphCmd) -> Result<()> {
+    let project = cmd.project.resolve()?;
+    let resolver = project.resolver();
+    let vendor_mods = project.vendor_modules()?.modules;
+    let graph = project.inspected_graph(&resolver, &vendor_mods)?;
+    let export = classify_graph(
+        &graph,
+        &GraphExportCtx {
+            entry_path: &project.input,
+            replaces: &project.replaces,
+            vendor_mods: &vendor_mods,
+            externals: &project.externals,
+            resolver: Some(&resolver),
+            normalizer: &project.normalizer,
+        },
+    );
+    let rendered = render_graph(&export, cmd.format);
+    match &cmd.output {
+        Some(path) => fs::write(path, rendered)
+            .with_context(|| format!("failed to write graph to {}", path.display())),
+        None => {
+            print!("{}", rendered);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! `luapack lint`: report undefined globals and global assignments in first-party modules.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{ArgAction, Parser};
+use luapack::{format_issues, lint_globals, resolve_pathbuf, LintCtx, LintSource};
+
+use crate::project::ProjectArgs;
+
+#[derive(Parser, Debug)]
+pub(crate) struct LintCmd {
+    #[command(flatten)]
+    project: ProjectArgs,
+
+    /// Dialect whose standard library is predefined: 5.1, 5.2, 5.3, 5.4, luajit or luau
+    #[arg(long)]
+    lua: Option<String>,
+
+    /// Globals provided by the host (e.g., `love`)
+    #[arg(long = "globals", value_name = "NAME", action = ArgAction::Append, value_delimiter = ',')]
+    globals: Vec<String>,
+
+    /// Stub files, or directories searched for `*.lua`, whose global assignments define host globals
+    #[arg(long = "library", value_name = "PATH", action = ArgAction::Append)]
+    library: Vec<PathBuf>,
+}
+
+pub(crate) fn run(cmd: LintCmd) -> Result<()> {
+    let project = cmd.project.resolve()?;
+    let dialect = project.dialect(cmd.lua.as_deref())?;
+    let resolver = project.resolver();
+    let vendor_modules = project.vendor_modules()?.modules;
+    let graph = project.inspected_graph(&resolver, &vendor_modules)?;
+
+    let cfg = &project.loaded.cfg;
+    let base = project.loaded.dir.as_deref();
+    let globals: Vec<String> = if !cmd.globals.is_empty() {
+        cmd.globals.clone()
+    } else {
+        cfg.globals.clone().unwrap_or_default()
+    };
+    let library: Vec<PathBuf> = if !cmd.library.is_empty() {
+        cmd.library.clone()
+    } else {
+        cfg.library
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|p| resolve_pathbuf(base, p))
+            .collect()
+    };
+
+    let mut providers: Vec<LintSource> = Vec::new();
+    for path in &project.preludes {
+        providers.push(read_source(None, path, "prelude")?);
+    }
+    for path in library_files(&library)? {
+        providers.push(read_source(None, &path, "library stub")?);
+    }
+    let mut vendored: Vec<(&String, &PathBuf)> = vendor_modules.iter().collect();
+    vendored.sort();
+    for (name, path) in vendored {
+        providers.push(read_source(Some(name), path, "vendor module")?);
+    }
+
+    let mut sources = vec![LintSource {
+        module: None,
+        path: project.input.clone(),
+        code: project.code.clone(),
+    }];
+    let mut modules: Vec<(&String, &PathBuf)> = graph.first_party.iter().collect();
+    modules.sort();
+    for (name, path) in modules {
+        sources.push(read_source(Some(name), path, "module")?);
+    }
+
+    let files = lint_globals(
+        &sources,
+        &LintCtx {
+            dialect,
+            host_globals: &globals,
+            providers: &providers,
+        },
+    );
+    if !files.is_empty() {
+        let count: usize = files.iter().map(|f| f.issues.len()).sum();
+        let listing: Vec<String> = files
+            .iter()
+            .map(|f| format_issues(&f.path, &f.issues))
+            .collect();
+        anyhow::bail!("{} problem(s) found:\n{}", count, listing.join("\n"));
+    }
+    eprintln!(
+        "lint ok: {} chunks checked (lua={})",
+        sources.len(),
+        dialect
+    );
+    Ok(())
+}
+
+fn read_source(module: Option<&String>, path: &Path, what: &str) -> Result<LintSource> {
+    let code = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}: {}", what, path.display()))?;
+    Ok(LintSource {
+        module: module.cloned(),
+        path: path.to_path_buf(),
+        code,
+    })
+}
+
+/// `--library` entries: files as given, directories expanded to their `*.lua` files.
+fn library_files(library: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    for entry in library {
+        if entry.is_file() {
+            out.push(entry.clone());
+            continue;
+        }
+        if !entry.is_dir() {
+            anyhow::bail!("library not found: {}", entry.display());
+        }
+        let pattern = entry.join("**").join("*.lua");
+        let pattern = pattern.to_string_lossy();
+        let mut files: Vec<PathBuf> = glob::glob(&pattern)
+            .with_context(|| format!("bad library path: {}", entry.display()))?
+            .flatten()
+            .filter(|p| p.is_file())
+            .collect();
+        files.sort();
+        out.extend(files);
+    }
+    Ok(out)
+}
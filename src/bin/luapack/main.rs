@@ -1,15 +1,38 @@
 #![allow(unexpected_cfgs)]
 #![allow(clippy::collapsible_if)]
-use std::{fs, path::PathBuf, process::ExitCode};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+mod graph;
+mod lint;
+mod project;
+mod run;
+mod unused;
+mod verify;
+mod watch;
+mod why;
 
 use anyhow::{Context, Result};
-use clap::{ArgAction, Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use luapack::{
-    BundleCtx, ModuleGraph, ModuleResolver, _BindRequireExport as BindRequire,
-    collect_vendor_modules, generate_bundle, infer_suffixes, load_config, parse_replace_rules,
-    parse_vendor_specs, resolve_pathbuf, BundleOptions, NameNormalizer,
+    _BindRequireExport as BindRequire, apply_vendor_shadows, format_issues,
+    generate_bundle_with_stats, generate_hot_update, parse_replace_rules, parse_sandbox_allow,
+    parse_vendor_specs, render_depfile, resolve_pathbuf, verify_bundle, BuildCache, BundleCtx,
+    BundleOptions, Instrument, LuaDialect, Sandbox, SandboxMode, SourceMap, Strip, VendorModules,
+    VendorShadowPolicy, VerifyCtx, IGNORE_FILE,
 };
 
+use crate::graph::GraphCmd;
+use crate::lint::LintCmd;
+use crate::project::ProjectArgs;
+use crate::run::RunCmd;
+use crate::unused::UnusedCmd;
+use crate::verify::VerifyCmd;
+use crate::why::WhyCmd;
+
 /// luapack: Lua bundler (Rust) — CLI
 #[derive(Parser, Debug)]
 #[command(
@@ -26,33 +49,52 @@ struct Cli {
 enum Commands {
     /// Bundle a Lua project according to paths/replaces/vendor
     Bundle(BundleCmd),
+    /// Print the module graph (requirer -> required) as DOT, JSON or Mermaid
+    Graph(GraphCmd),
+    /// Explain why a module is bundled, resolved or rewritten the way it is
+    Why(WhyCmd),
+    /// List first-party modules that are never required from the entry
+    Unused(UnusedCmd),
+    /// Statically check a generated bundle
+    Verify(VerifyCmd),
+    /// Report undefined globals and global assignments in first-party modules
+    Lint(LintCmd),
+    /// Bundle in memory and run the result in the embedded Lua VM
+    Run(RunCmd),
 }
 
 #[derive(Parser, Debug)]
 struct BundleCmd {
-    /// Entry Lua source file (e.g., lua/main.lua)
-    #[arg(value_name = "INPUT")]
-    input: PathBuf,
+    #[command(flatten)]
+    project: ProjectArgs,
 
-    /// Lua version (informational for now)
+    /// Target Lua dialect: 5.1, 5.2, 5.3, 5.4, luajit or luau
     #[arg(long)]
     lua: Option<String>,
 
-    /// First-party bundle roots (Lua-style search paths)
-    #[arg(long = "path", value_name = "PATTERN", action = ArgAction::Append)]
-    paths: Vec<String>,
+    /// Remove Luau type annotations, type declarations and casts from every chunk
+    #[arg(long = "strip-types", action = ArgAction::SetTrue, default_value_t = false)]
+    strip_types: bool,
+
+    /// Remove debug-only statements from first-party modules: assert (`assert(...)` calls)
+    #[arg(long = "strip", value_enum, value_name = "WHAT", action = ArgAction::Append)]
+    strip: Vec<Strip>,
 
-    /// Prelude files injected before modules execute (order preserved)
-    #[arg(long = "prelude", value_name = "FILE", action = ArgAction::Append)]
-    preludes: Vec<PathBuf>,
+    /// Remove regions between `--@debug begin` and `--@debug end` comments
+    #[arg(long = "strip-marked", action = ArgAction::SetTrue, default_value_t = false)]
+    strip_marked: bool,
 
-    /// Replace rules (syntax per docs; parsed later)
-    #[arg(long = "replace", value_name = "RULE", action = ArgAction::Append)]
-    replace: Vec<String>,
+    /// Rewrite newer syntax (`//`, bitwise operators, `<const>`) for the `--lua` target
+    #[arg(long = "downlevel", action = ArgAction::SetTrue, default_value_t = false)]
+    downlevel: bool,
 
-    /// Vendor include roots (syntax per docs; parsed later)
-    #[arg(long = "vendor", value_name = "SPEC", action = ArgAction::Append)]
-    vendor: Vec<String>,
+    /// Skip checking every chunk against the `--lua` dialect's syntax
+    #[arg(long = "no-validate", action = ArgAction::SetTrue, default_value_t = false)]
+    no_validate: bool,
+
+    /// Check the finished bundle like `luapack verify` before writing it
+    #[arg(long = "verify", action = ArgAction::SetTrue, default_value_t = false)]
+    verify: bool,
 
     /// Output bundle file path
     #[arg(short = 'o', long = "output", value_name = "FILE")]
@@ -66,13 +108,53 @@ struct BundleCmd {
     #[arg(long = "bind-require", value_enum)]
     bind_require: Option<BindRequire>,
 
+    /// Log every module load with its requester and loader time; collected in `__LUAPACK_PROFILE`
+    #[arg(long = "runtime-trace", action = ArgAction::SetTrue, default_value_t = false)]
+    runtime_trace: bool,
+
+    /// Expose the read-only `__LUAPACK` table (modules, load state, version, build id) at runtime
+    #[arg(long = "introspect", action = ArgAction::SetTrue, default_value_t = false)]
+    introspect: bool,
+
+    /// Dev runtime that can swap modules at runtime; with --watch, also writes <output>.hot.lua
+    #[arg(long = "hot-reload", action = ArgAction::SetTrue, default_value_t = false)]
+    hot_reload: bool,
+
+    /// Write only a hot update with these modules to --output, for a running --hot-reload bundle
+    #[arg(long = "hot-update", value_name = "MODULE", action = ArgAction::Append)]
+    hot_update: Vec<String>,
+
+    /// Run each module in its own environment; global assignments raise an error or warn
+    #[arg(long = "sandbox", value_enum, value_name = "MODE")]
+    sandbox: Option<SandboxMode>,
+
+    /// Globals modules under a prefix may assign: PREFIX=GLOBAL[,GLOBAL...] (`*` for every module)
+    #[arg(long = "sandbox-allow", value_name = "RULE", action = ArgAction::Append)]
+    sandbox_allow: Vec<String>,
+
+    /// Instrument first-party modules at runtime: coverage (line hits in `__LUAPACK_COVERAGE`)
+    #[arg(long = "instrument", value_enum, value_name = "MODE")]
+    instrument: Option<Instrument>,
+
     /// Print extra information about parsing
     #[arg(long = "diagnostics", action = ArgAction::SetTrue, default_value_t = false)]
     diagnostics: bool,
 
-    /// Path to config file; if omitted, auto-discovers luapack.{toml,yaml,yml,json}
-    #[arg(long = "config", value_name = "FILE")]
-    config: Option<PathBuf>,
+    /// Keep running and rebuild the bundle whenever one of its inputs changes
+    #[arg(long = "watch", action = ArgAction::SetTrue, default_value_t = false)]
+    watch: bool,
+
+    /// Write a Make/ninja depfile listing every file the bundle was built from
+    #[arg(long = "depfile", value_name = "FILE")]
+    depfile: Option<PathBuf>,
+
+    /// Cache scanned requires and transformed chunks of unchanged files between builds
+    #[arg(long = "cache", action = ArgAction::SetTrue, default_value_t = false)]
+    cache: bool,
+
+    /// Cache directory for --cache (default: .luapack-cache next to the config, or in the working directory)
+    #[arg(long = "cache-dir", value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
 
     /// Base directory to redact absolute paths in bundle comments
     #[arg(long = "redact-base", value_name = "DIR")]
@@ -93,101 +175,126 @@ fn real_main() -> Result<()> {
     let cli = Cli::parse();
     match cli.command {
         Commands::Bundle(cmd) => run_bundle(cmd),
-    }?;
-
-    // TODO: future steps — watch mode
-    Ok(())
+        Commands::Graph(cmd) => graph::run(cmd),
+        Commands::Why(cmd) => why::run(cmd),
+        Commands::Unused(cmd) => unused::run(cmd),
+        Commands::Verify(cmd) => verify::run(cmd),
+        Commands::Lint(cmd) => lint::run(cmd),
+        Commands::Run(cmd) => run::run(cmd),
+    }
 }
 
 fn run_bundle(cli: BundleCmd) -> Result<()> {
-    // Load configuration (explicit or auto-discovered)
-    let loaded = load_config(cli.config.as_deref())?;
-    let base = loaded.dir.as_deref();
+    if cli.watch {
+        return watch::watch(&cli);
+    }
+    build_bundle(&cli).map(|_| ())
+}
+
+/// Files and module search templates a build depended on (for `--depfile` and `--watch`).
+#[derive(Debug, Default, Clone)]
+struct BuildInputs {
+    files: Vec<PathBuf>,
+    templates: Vec<String>,
+}
 
-    // Read entry source
-    let code = fs::read_to_string(&cli.input)
-        .with_context(|| format!("failed to read input: {}", cli.input.display()))?;
+/// A finished build: what it read and, when generated, the bundle with its source map.
+#[cfg_attr(
+    not(feature = "run"),
+    expect(
+        dead_code,
+        reason = "only `luapack run` reads the bundle and its dialect"
+    )
+)]
+struct Build {
+    inputs: BuildInputs,
+    bundle: Option<(String, SourceMap)>,
+    /// The dialect the bundle targets, after config and `--lua`.
+    dialect: LuaDialect,
+}
+
+fn build_bundle(cli: &BundleCmd) -> Result<BuildInputs> {
+    build(cli, false, &[], None).map(|b| b.inputs)
+}
+
+/// Build the bundle. With `in_memory`, the bundle is always generated and only written
+/// when `--output` is given; the config's `output` is ignored.
+///
+/// `changed` lists the (canonical) files changed since the previous build; with
+/// `--hot-reload`, their modules are also written as a hot update next to the output.
+///
+/// `memory` is a cache kept across builds by the caller; it is used when `--cache` is off.
+fn build(
+    cli: &BundleCmd,
+    in_memory: bool,
+    changed: &[PathBuf],
+    memory: Option<&mut BuildCache>,
+) -> Result<Build> {
+    let project = cli.project.resolve()?;
+    let loaded = &project.loaded;
+    let base = loaded.dir.as_deref();
+    let code = &project.code;
+    let paths = &project.paths;
+    let preludes = &project.preludes;
+    let normalizer = &project.normalizer;
 
     // Effective options: config < env < CLI (CLI overrides). For booleans, CLI true wins; false doesn't cancel config.
-    let lua_ver = cli
-        .lua
-        .clone()
-        .or(loaded.cfg.lua.clone())
-        .unwrap_or_else(|| "5.1".to_string());
-
-    // Warn if selected dialect likely unsupported by this build
-    {
-        let v = lua_ver.to_ascii_lowercase();
-        let needs = if v.contains("luajit") {
-            Some(("luajit", cfg!(feature = "luajit")))
-        } else if v.starts_with("5.4") || v == "54" {
-            Some(("lua54", cfg!(feature = "lua54")))
-        } else if v.starts_with("5.3") || v == "53" {
-            Some(("lua53", cfg!(feature = "lua53")))
-        } else if v.starts_with("5.2") || v == "52" {
-            Some(("lua52", cfg!(feature = "lua52")))
-        } else {
-            None // 5.1 baseline
-        };
-        if let Some((feat, built)) = needs {
-            if !built {
-                eprintln!(
-                    "warning: requested Lua dialect requires feature '{feat}', but this binary was built without it.\n         Rebuild with: cargo build --features {feat}"
-                );
-            }
-        }
-    }
+    let lua_ver = project.dialect(cli.lua.as_deref())?;
 
-    let paths: Vec<String> = if !cli.paths.is_empty() {
-        cli.paths.clone()
+    let strip_types = cli.strip_types || loaded.cfg.strip_types.unwrap_or(false);
+    if strip_types && !cfg!(feature = "luau") {
+        eprintln!(
+            "warning: --strip-types requires feature 'luau', but this binary was built without it.\n         Rebuild with: cargo build --features luau"
+        );
+    }
+    let strip: Vec<Strip> = if !cli.strip.is_empty() {
+        cli.strip.clone()
     } else {
         loaded
             .cfg
-            .paths
+            .strip
             .clone()
             .unwrap_or_default()
-            .into_iter()
-            .map(|p| {
-                // resolve relative to config file dir
-                resolve_pathbuf(base, &p).to_string_lossy().to_string()
+            .iter()
+            .map(|s| {
+                Strip::from_str(s, true)
+                    .map_err(|_| anyhow::anyhow!("invalid strip target in config: {}", s))
             })
-            .collect()
+            .collect::<Result<_>>()?
     };
+    let strip_asserts = strip.contains(&Strip::Assert);
+    let strip_marked = cli.strip_marked || loaded.cfg.strip_marked.unwrap_or(false);
+    let downlevel = cli.downlevel || loaded.cfg.downlevel.unwrap_or(false);
+    let validate = !cli.no_validate && loaded.cfg.validate.unwrap_or(true);
+    let verify = cli.verify || loaded.cfg.verify.unwrap_or(false);
 
-    let preludes: Vec<PathBuf> = if !cli.preludes.is_empty() {
-        cli.preludes.clone()
+    let output_path: Option<PathBuf> = if let Some(o) = &cli.output {
+        Some(o.clone())
+    } else if in_memory {
+        None
     } else {
         loaded
             .cfg
-            .preludes
-            .clone()
-            .unwrap_or_default()
-            .into_iter()
-            .map(|p| resolve_pathbuf(base, &p))
-            .collect()
-    };
-
-    let replaces_vec: Vec<String> = if !cli.replace.is_empty() {
-        cli.replace.clone()
-    } else {
-        loaded.cfg.replace.clone().unwrap_or_default()
-    };
-
-    let vendors_vec: Vec<String> = if !cli.vendor.is_empty() {
-        cli.vendor.clone()
-    } else {
-        loaded.cfg.vendors.clone().unwrap_or_default()
+            .output
+            .as_deref()
+            .map(|s| resolve_pathbuf(base, s))
     };
 
-    let output_path: Option<PathBuf> = if let Some(o) = &cli.output {
-        Some(o.clone())
+    let depfile_path: Option<PathBuf> = if let Some(d) = &cli.depfile {
+        Some(d.clone())
     } else {
         loaded
             .cfg
-            .output
+            .depfile
             .as_deref()
             .map(|s| resolve_pathbuf(base, s))
     };
+    if !cli.hot_update.is_empty() && (in_memory || output_path.is_none()) {
+        anyhow::bail!("--hot-update requires an output path (--output or `output` in the config)");
+    }
+    if depfile_path.is_some() && output_path.is_none() && !in_memory {
+        anyhow::bail!("--depfile requires an output path (--output or `output` in the config)");
+    }
 
     let entry_mod = cli.entry.clone().or(loaded.cfg.entry.clone());
 
@@ -203,6 +310,37 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
         BindRequire::Router
     };
 
+    let runtime_trace = cli.runtime_trace || loaded.cfg.runtime_trace.unwrap_or(false);
+    let introspect = cli.introspect || loaded.cfg.introspect.unwrap_or(false);
+    let hot_reload = cli.hot_reload || loaded.cfg.hot_reload.unwrap_or(false);
+    let sandbox_mode = match (cli.sandbox, loaded.cfg.sandbox.as_deref()) {
+        (Some(mode), _) => Some(mode),
+        (None, Some(s)) => Some(
+            SandboxMode::from_str(s, true)
+                .map_err(|_| anyhow::anyhow!("invalid sandbox mode in config: {}", s))?,
+        ),
+        (None, None) => None,
+    };
+    let sandbox_allow_flags: Vec<String> = if !cli.sandbox_allow.is_empty() {
+        cli.sandbox_allow.clone()
+    } else {
+        loaded.cfg.sandbox_allow.clone().unwrap_or_default()
+    };
+    let sandbox = match sandbox_mode {
+        Some(mode) => Some(Sandbox {
+            mode,
+            allow: parse_sandbox_allow(&sandbox_allow_flags)?,
+        }),
+        None => None,
+    };
+    let instrument = match (cli.instrument, loaded.cfg.instrument.as_deref()) {
+        (Some(mode), _) => Some(mode),
+        (None, Some(s)) => Some(
+            Instrument::from_str(s, true)
+                .map_err(|_| anyhow::anyhow!("invalid instrument mode in config: {}", s))?,
+        ),
+        (None, None) => None,
+    };
     let diagnostics = cli.diagnostics || loaded.cfg.diagnostics.unwrap_or(false);
 
     // Determine redaction base path
@@ -216,29 +354,36 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
             .map(|s| resolve_pathbuf(base, s))
     };
 
-    // Parse replace/vendor flags up-front; use them later for bundling
-    let parsed_replaces = parse_replace_rules(&replaces_vec).unwrap_or_default();
-    let parsed_vendors = parse_vendor_specs(&vendors_vec).unwrap_or_default();
-    // Compute suffix normalization early
-    let vendor_paths: Vec<String> = parsed_vendors
-        .iter()
-        .flat_map(|v| v.paths.iter().cloned())
-        .collect();
-    let vendor_suffixes: Vec<String> = parsed_vendors
-        .iter()
-        .flat_map(|v| v.suffixes.iter().cloned())
-        .collect();
-    let suffixes = infer_suffixes(&paths, &vendor_paths, &vendor_suffixes, &parsed_replaces);
-    let normalizer = NameNormalizer::new(suffixes);
+    let cache_dir: Option<PathBuf> = if cli.cache || loaded.cfg.cache.unwrap_or(false) {
+        Some(match &cli.cache_dir {
+            Some(d) => d.clone(),
+            None => resolve_pathbuf(
+                base,
+                loaded.cfg.cache_dir.as_deref().unwrap_or(".luapack-cache"),
+            ),
+        })
+    } else {
+        None
+    };
 
     let bundle_opts = BundleOptions {
-        lua: lua_ver.clone(),
+        lua: lua_ver,
+        strip_types,
+        strip_asserts,
+        strip_marked,
+        downlevel,
+        validate,
         paths: paths.clone(),
         preludes: preludes.clone(),
-        replaces: parsed_replaces.clone(),
-        vendor_specs: parsed_vendors.clone(),
+        replaces: project.replaces.clone(),
+        vendor_specs: project.vendors.clone(),
         entry: entry_mod.clone(),
         bind: bind_mode,
+        runtime_trace,
+        introspect,
+        hot_reload,
+        sandbox,
+        instrument,
         diagnostics,
         redact_base: redact_base_path.clone(),
         normalizer: normalizer.clone(),
@@ -246,7 +391,7 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
 
     // Diagnostics (optional): show parsed info and simple resolution
     if diagnostics {
-        eprintln!("parsed ok: {} (lua={})", cli.input.display(), lua_ver);
+        eprintln!("parsed ok: {} (lua={})", project.input.display(), lua_ver);
         if !paths.is_empty() {
             eprintln!("paths: {}", paths.join(", "));
         }
@@ -260,7 +405,7 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
                     .join(", ")
             );
         }
-        match &parse_replace_rules(&replaces_vec) {
+        match &parse_replace_rules(&project.replace_flags) {
             Ok(rules) if !rules.is_empty() => {
                 eprintln!("replace rules ({}):", rules.len());
                 for r in rules {
@@ -273,7 +418,7 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
             Err(e) => eprintln!("warning: replace parse error: {e}"),
             _ => {}
         }
-        match &parse_vendor_specs(&vendors_vec) {
+        match &parse_vendor_specs(&project.vendor_flags) {
             Ok(specs) if !specs.is_empty() => {
                 eprintln!("vendor specs ({}):", specs.len());
                 for v in specs {
@@ -281,6 +426,25 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
                         "  paths={:?} exclude_name={:?} exclude_prefix={:?}",
                         v.paths, v.exclude_names, v.exclude_prefixes
                     );
+                    let globs = |ps: &[glob::Pattern]| -> Vec<String> {
+                        ps.iter().map(|p| p.to_string()).collect()
+                    };
+                    if !v.include_names.is_empty() || !v.include_globs.is_empty() {
+                        eprintln!(
+                            "    include_name={:?} include_glob={:?}",
+                            v.include_names,
+                            globs(&v.include_globs)
+                        );
+                    }
+                    if !v.exclude_globs.is_empty() || !v.exclude_regexes.is_empty() {
+                        let regexes: Vec<&str> =
+                            v.exclude_regexes.iter().map(|r| r.as_str()).collect();
+                        eprintln!(
+                            "    exclude_glob={:?} exclude_regex={:?}",
+                            globs(&v.exclude_globs),
+                            regexes
+                        );
+                    }
                 }
             }
             Err(e) => eprintln!("warning: vendor parse error: {e}"),
@@ -289,24 +453,25 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
         if let Some(out) = &output_path {
             eprintln!("output: {}", out.display());
         }
-        let resolver = ModuleResolver::new(paths.clone());
-        let requires = luapack::find_literal_requires(&code);
+        let resolver = project.resolver();
+        let graph = project.graph(&resolver, None);
+        let requires = luapack::find_literal_requires(code);
         if !requires.is_empty() {
             eprintln!("require literals found ({}):", requires.len());
             for r in requires {
                 eprintln!(
                     "  {}:{}:{} -> {}",
-                    cli.input.display(),
+                    project.input.display(),
                     r.line,
                     r.col,
                     r.module
                 );
-                match resolver.resolve(&r.module) {
-                    Some(path) => eprintln!("    resolved: {}", path.display()),
-                    None => eprintln!("    unresolved with given --path templates"),
+                match (resolver.resolve(&r.module), resolver.excluded(&r.module)) {
+                    (Some(path), _) => eprintln!("    resolved: {}", path.display()),
+                    (None, Some((_, path))) => eprintln!("    excluded: {}", path.display()),
+                    (None, None) => eprintln!("    unresolved with given --path templates"),
                 }
             }
-            let graph = ModuleGraph::build_from_entry_code(&code, &resolver, &normalizer);
             eprintln!(
                 "graph: first_party={} unresolved={}",
                 graph.first_party.len(),
@@ -319,17 +484,117 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
                 }
             }
         }
+        if !graph.excluded.is_empty() {
+            let mut excluded: Vec<(&String, &PathBuf)> = graph.excluded.iter().collect();
+            excluded.sort();
+            eprintln!("excluded modules ({}), left to the host:", excluded.len());
+            for (name, path) in excluded {
+                eprintln!("  {} -> {}", name, path.display());
+            }
+        }
+        if !project.includes.is_empty() {
+            eprintln!("included modules ({}):", project.includes.len());
+            for (name, path) in &project.includes {
+                eprintln!("  {} -> {}", name, path.display());
+            }
+        }
+        let mut units: Vec<(&Path, &str)> = graph
+            .units
+            .values()
+            .map(|u| (u.path.as_path(), u.source.as_str()))
+            .collect();
+        units.sort();
+        let mut dynamic: Vec<String> = Vec::new();
+        for (path, source) in std::iter::once((project.input.as_path(), code.as_str())).chain(units)
+        {
+            for d in luapack::find_dynamic_requires(source) {
+                dynamic.push(format!(
+                    "  {}:{}:{}: require({})",
+                    path.display(),
+                    d.line,
+                    d.col,
+                    d.argument
+                ));
+            }
+        }
+        if !dynamic.is_empty() {
+            eprintln!(
+                "dynamic requires ({}), hint their modules with --include:",
+                dynamic.len()
+            );
+            for line in dynamic {
+                eprintln!("{line}");
+            }
+        }
     }
 
+    let ignore_file = loaded
+        .dir
+        .clone()
+        .or_else(|| std::env::current_dir().ok())
+        .map(|dir| dir.join(IGNORE_FILE));
+    let mut inputs = BuildInputs {
+        files: std::iter::once(project.input.clone())
+            .chain(preludes.iter().cloned())
+            .chain(loaded.path.clone())
+            .chain(ignore_file.clone())
+            .collect(),
+        templates: paths
+            .iter()
+            .chain(project.vendor_paths.iter())
+            .cloned()
+            .collect(),
+    };
+
     // If output is requested, emit a minimal bundle: runtime loader + first-party + vendor + root + preludes
-    if let Some(out_path) = &output_path {
-        let resolver = ModuleResolver::new(paths.clone());
-        let graph = ModuleGraph::build_from_entry_code(&code, &resolver, &normalizer);
-        let (vendor_mods, vendor_dups) =
-            collect_vendor_modules(&parsed_vendors, &parsed_replaces, &normalizer)?;
+    let mut generated = None;
+    if output_path.is_some() || in_memory {
+        let fingerprint = BuildCache::fingerprint(&bundle_opts);
+        let disk;
+        let cache: Option<&BuildCache> = match (cache_dir.as_deref(), memory) {
+            (Some(dir), _) => {
+                disk = BuildCache::open(dir, &fingerprint);
+                Some(&disk)
+            }
+            (None, Some(memory)) => {
+                memory.begin_build(&fingerprint);
+                Some(memory)
+            }
+            (None, None) => None,
+        };
+        let resolver = project.resolver();
+        let mut graph = project.graph(&resolver, cache);
+        let VendorModules {
+            modules: vendor_mods,
+            duplicates: vendor_dups,
+            conflicts: vendor_conflicts,
+        } = project.vendor_modules()?;
+        for c in &vendor_conflicts {
+            eprintln!("warning: {}; bundling {}", c, c.kept.display());
+        }
+        for s in apply_vendor_shadows(&mut graph, &vendor_mods, project.vendor_shadow)? {
+            let bundled = match project.vendor_shadow {
+                VendorShadowPolicy::Vendor => &s.vendor,
+                _ => &s.first_party,
+            };
+            eprintln!("warning: {}; bundling {}", s, bundled.display());
+        }
+        if diagnostics && !graph.aliases.is_empty() {
+            let mut aliases: Vec<_> = graph.aliases.iter().collect();
+            aliases.sort();
+            eprintln!("module aliases ({}):", aliases.len());
+            for (alias, name) in aliases {
+                eprintln!("  {} -> {}", alias, name);
+            }
+        }
         if diagnostics && !vendor_dups.is_empty() {
-            eprintln!("vendor duplicate module names ({}):", vendor_dups.len());
-            for n in vendor_dups {
+            let mut dups: Vec<_> = vendor_dups.into_iter().collect();
+            dups.sort();
+            eprintln!(
+                "vendor duplicate module names, identical content ({}):",
+                dups.len()
+            );
+            for n in dups {
                 eprintln!("  - {}", n);
             }
         }
@@ -341,23 +606,156 @@ fn run_bundle(cli: BundleCmd) -> Result<()> {
             entry: bundle_opts.entry.as_deref(),
             replaces: &bundle_opts.replaces,
             vendor_mods: &vendor_mods,
-            entry_source: &code,
-            entry_path: &cli.input,
+            entry_source: code,
+            entry_path: &project.input,
             bind: bundle_opts.bind,
+            runtime_trace: bundle_opts.runtime_trace,
+            introspect: bundle_opts.introspect,
+            hot_reload: bundle_opts.hot_reload,
+            sandbox: bundle_opts.sandbox.as_ref(),
+            instrument: bundle_opts.instrument,
             resolver: Some(&resolver),
             redact_base: bundle_opts.redact_base.clone(),
             normalizer: &bundle_opts.normalizer,
+            dialect: bundle_opts.lua,
+            strip_types: bundle_opts.strip_types,
+            strip_asserts: bundle_opts.strip_asserts,
+            strip_marked: bundle_opts.strip_marked,
+            downlevel: bundle_opts.downlevel,
+            validate: bundle_opts.validate,
+            cache,
         };
-        let (bundle, total_rewrites) = generate_bundle(&graph, ctx)?;
+        let mut hot_modules: Vec<String> = cli.hot_update.clone();
+        if hot_reload && !changed.is_empty() {
+            let mut named: Vec<(&String, &PathBuf)> =
+                graph.first_party.iter().chain(vendor_mods.iter()).collect();
+            named.sort();
+            for (name, path) in named {
+                let key = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+                if changed.contains(&key) && !hot_modules.contains(name) {
+                    hot_modules.push(name.clone());
+                }
+            }
+        }
+        let hot_update = if hot_modules.is_empty() {
+            None
+        } else {
+            Some(generate_hot_update(&graph, &ctx, &hot_modules)?)
+        };
+        if !cli.hot_update.is_empty() {
+            // Only the update was asked for: it replaces the bundle.
+            if let (Some(out_path), Some(update)) = (&output_path, &hot_update) {
+                write_file(out_path, update, "hot update")?;
+            }
+            return Ok(Build {
+                inputs,
+                bundle: None,
+                dialect: lua_ver,
+            });
+        }
+        let (bundle, stats) = generate_bundle_with_stats(&graph, ctx)?;
         if diagnostics {
-            eprintln!("bundle literal rewrites: {}", total_rewrites);
+            eprintln!("bundle literal rewrites: {}", stats.rewrites);
+            if bundle_opts.strip_types {
+                eprintln!("bundle type annotations stripped: {}", stats.types_stripped);
+            }
+            if bundle_opts.strip_asserts || bundle_opts.strip_marked {
+                eprintln!("bundle debug code stripped: {}", stats.debug_stripped);
+            }
+            if bundle_opts.downlevel {
+                eprintln!(
+                    "bundle downlevel rewrites: {} (lua={})",
+                    stats.downlevel_rewrites, bundle_opts.lua
+                );
+            }
+            if !stats.vendor_shared.is_empty() {
+                eprintln!(
+                    "vendor modules sharing a loader ({}):",
+                    stats.vendor_shared.len()
+                );
+                for (name, first) in &stats.vendor_shared {
+                    eprintln!("  {} -> {}", name, first);
+                }
+            }
+        }
+        if let Some(cache) = cache {
+            if diagnostics {
+                eprintln!("cache: {} hits, {} misses", cache.hits(), cache.misses());
+            }
+            cache.save()?;
+        }
+        if verify {
+            let report = verify_bundle(
+                &bundle,
+                &VerifyCtx {
+                    dialect: lua_ver,
+                    replaces: &project.replaces,
+                    externals: &project.externals,
+                    resolver: Some(&resolver),
+                    normalizer,
+                },
+            );
+            if !report.issues.is_empty() {
+                let shown = output_path.as_deref().unwrap_or(Path::new("<bundle>"));
+                anyhow::bail!(
+                    "bundle failed verification:\n{}",
+                    format_issues(shown, &report.issues)
+                );
+            }
+        }
+        if let Some(out_path) = &output_path {
+            write_file(out_path, &bundle, "bundle")?;
+            if let Some(update) = &hot_update {
+                let hot_path = out_path.with_extension("hot.lua");
+                write_file(&hot_path, update, "hot update")?;
+                eprintln!(
+                    "hot update: {} -> {}",
+                    hot_modules.join(", "),
+                    hot_path.display()
+                );
+            }
         }
-        if let Some(parent) = out_path.parent() {
-            fs::create_dir_all(parent)?;
+
+        let mut first_party: Vec<PathBuf> = graph.first_party.values().cloned().collect();
+        first_party.sort();
+        let mut vendor: Vec<PathBuf> = vendor_mods.values().cloned().collect();
+        vendor.sort();
+        inputs.files = std::iter::once(project.input.clone())
+            .chain(first_party)
+            .chain(vendor)
+            .chain(bundle_opts.preludes.iter().cloned())
+            .chain(loaded.path.clone())
+            .chain(ignore_file)
+            .collect();
+
+        if let (Some(dep_path), Some(out_path)) = (&depfile_path, &output_path) {
+            if let Some(parent) = dep_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // The ignore file is watched before it exists, but make needs real prerequisites.
+            let existing: Vec<PathBuf> = inputs
+                .files
+                .iter()
+                .filter(|p| p.exists())
+                .cloned()
+                .collect();
+            fs::write(dep_path, render_depfile(out_path, &existing))
+                .with_context(|| format!("failed to write depfile to {}", dep_path.display()))?;
         }
-        fs::write(out_path, bundle)
-            .with_context(|| format!("failed to write bundle to {}", out_path.display()))?;
+        generated = Some((bundle, stats.source_map));
     }
 
-    Ok(())
+    Ok(Build {
+        inputs,
+        bundle: generated,
+        dialect: lua_ver,
+    })
+}
+
+fn write_file(path: &Path, contents: &str, what: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+        .with_context(|| format!("failed to write {} to {}", what, path.display()))
 }
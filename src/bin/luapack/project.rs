@@ -0,0 +1,296 @@
+//! Options shared by every subcommand that walks a project: entry, search paths,
+//! replace rules, vendor roots and the config file they may come from.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{ArgAction, Args, ValueEnum};
+use glob::Pattern;
+use luapack::{
+    apply_vendor_shadows, collect_vendor_set, exclude_patterns, expand_includes, infer_suffixes,
+    load_config, parse_replace_rules, parse_vendor_specs, read_ignore_file, resolve_pathbuf,
+    BuildCache, LoadedConfig, LuaDialect, ModuleGraph, ModuleResolver, NameNormalizer, ReplaceRule,
+    VendorConflictPolicy, VendorModules, VendorShadowPolicy, VendorSpec,
+};
+
+#[derive(Args, Debug)]
+pub(crate) struct ProjectArgs {
+    /// Entry Lua source file (e.g., lua/main.lua)
+    #[arg(value_name = "INPUT")]
+    pub input: PathBuf,
+
+    #[command(flatten)]
+    pub options: ProjectOptions,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ProjectOptions {
+    /// First-party bundle roots (Lua-style search paths)
+    #[arg(long = "path", value_name = "PATTERN", action = ArgAction::Append)]
+    pub paths: Vec<String>,
+
+    /// Prelude files injected before modules execute (order preserved)
+    #[arg(long = "prelude", value_name = "FILE", action = ArgAction::Append)]
+    pub preludes: Vec<PathBuf>,
+
+    /// Replace rules (syntax per docs; parsed later)
+    #[arg(long = "replace", value_name = "RULE", action = ArgAction::Append)]
+    pub replace: Vec<String>,
+
+    /// Vendor include roots (syntax per docs; parsed later)
+    #[arg(long = "vendor", value_name = "SPEC", action = ArgAction::Append)]
+    pub vendor: Vec<String>,
+
+    /// Vendor file bundled when two vendor roots ship the same module with different content
+    #[arg(long = "vendor-conflict", value_name = "POLICY", value_enum)]
+    pub vendor_conflict: Option<VendorConflictPolicy>,
+
+    /// Module bundled when a first-party module has the same name as a vendor module
+    #[arg(long = "vendor-shadow", value_name = "POLICY", value_enum)]
+    pub vendor_shadow: Option<VendorShadowPolicy>,
+
+    /// Modules provided by the host at runtime, as module-name globs (e.g. `love.*`)
+    #[arg(long = "external", value_name = "GLOB", action = ArgAction::Append)]
+    pub externals: Vec<String>,
+
+    /// Modules to bundle even if no literal require reaches them, as module-name globs
+    /// (e.g. `widgets.*`, `themes.**`), for requires whose name is computed at run time
+    #[arg(long = "include", value_name = "GLOB", action = ArgAction::Append)]
+    pub include: Vec<String>,
+
+    /// First-party files never bundled, even when required, as path globs (e.g.
+    /// `lua/**/test_*.lua`); their modules are left to the host like externals
+    #[arg(long = "exclude", value_name = "GLOB", action = ArgAction::Append)]
+    pub exclude: Vec<String>,
+
+    /// Path to config file; if omitted, auto-discovers luapack.{toml,yaml,yml,json}
+    #[arg(long = "config", value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// [`ProjectArgs`] merged with the config file (CLI values win) and parsed.
+pub(crate) struct Project {
+    pub loaded: LoadedConfig,
+    pub input: PathBuf,
+    /// Source of `input`.
+    pub code: String,
+    pub paths: Vec<String>,
+    pub preludes: Vec<PathBuf>,
+    /// Replace and vendor flags as given, for diagnostics.
+    pub replace_flags: Vec<String>,
+    pub vendor_flags: Vec<String>,
+    pub replaces: Vec<ReplaceRule>,
+    pub vendors: Vec<VendorSpec>,
+    pub vendor_paths: Vec<String>,
+    pub vendor_conflict: VendorConflictPolicy,
+    pub vendor_shadow: VendorShadowPolicy,
+    pub externals: Vec<String>,
+    /// Modules matched by the `--include` globs, walked as extra graph roots.
+    pub includes: Vec<(String, PathBuf)>,
+    /// `--exclude` (or config `exclude`) globs plus the `.luapackignore` file, made absolute.
+    pub excludes: Vec<Pattern>,
+    pub normalizer: NameNormalizer,
+}
+
+impl ProjectArgs {
+    pub(crate) fn resolve(&self) -> Result<Project> {
+        self.options.resolve(&self.input)
+    }
+}
+
+impl ProjectOptions {
+    /// Merge with the config file and read `input` (the entry, or the bundle for `verify`).
+    pub(crate) fn resolve(&self, input: &Path) -> Result<Project> {
+        // Load configuration (explicit or auto-discovered)
+        let loaded = load_config(self.config.as_deref())?;
+        let base = loaded.dir.as_deref();
+
+        let code = fs::read_to_string(input)
+            .with_context(|| format!("failed to read input: {}", input.display()))?;
+
+        let paths: Vec<String> = if !self.paths.is_empty() {
+            self.paths.clone()
+        } else {
+            loaded
+                .cfg
+                .paths
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| {
+                    // resolve relative to config file dir
+                    resolve_pathbuf(base, &p).to_string_lossy().to_string()
+                })
+                .collect()
+        };
+
+        let preludes: Vec<PathBuf> = if !self.preludes.is_empty() {
+            self.preludes.clone()
+        } else {
+            loaded
+                .cfg
+                .preludes
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|p| resolve_pathbuf(base, &p))
+                .collect()
+        };
+
+        let replace_flags: Vec<String> = if !self.replace.is_empty() {
+            self.replace.clone()
+        } else {
+            loaded.cfg.replace.clone().unwrap_or_default()
+        };
+
+        let vendor_flags: Vec<String> = if !self.vendor.is_empty() {
+            self.vendor.clone()
+        } else {
+            loaded.cfg.vendors.clone().unwrap_or_default()
+        };
+
+        let vendor_conflict = match (self.vendor_conflict, loaded.cfg.vendor_conflict.as_deref()) {
+            (Some(policy), _) => policy,
+            (None, Some(s)) => VendorConflictPolicy::from_str(s, true)
+                .map_err(|_| anyhow::anyhow!("invalid vendor_conflict in config: {}", s))?,
+            (None, None) => VendorConflictPolicy::default(),
+        };
+        let vendor_shadow = match (self.vendor_shadow, loaded.cfg.vendor_shadow.as_deref()) {
+            (Some(policy), _) => policy,
+            (None, Some(s)) => VendorShadowPolicy::from_str(s, true)
+                .map_err(|_| anyhow::anyhow!("invalid vendor_shadow in config: {}", s))?,
+            (None, None) => VendorShadowPolicy::default(),
+        };
+
+        let externals: Vec<String> = if !self.externals.is_empty() {
+            self.externals.clone()
+        } else {
+            loaded.cfg.externals.clone().unwrap_or_default()
+        };
+
+        // Parse replace/vendor flags up-front; use them later for bundling
+        let replaces = parse_replace_rules(&replace_flags).unwrap_or_default();
+        let vendors = parse_vendor_specs(&vendor_flags)?;
+        // Compute suffix normalization early
+        let vendor_paths: Vec<String> = vendors
+            .iter()
+            .flat_map(|v| v.paths.iter().cloned())
+            .collect();
+        let vendor_suffixes: Vec<String> = vendors
+            .iter()
+            .flat_map(|v| v.suffixes.iter().cloned())
+            .collect();
+        let suffixes = infer_suffixes(&paths, &vendor_paths, &vendor_suffixes, &replaces);
+        let normalizer = NameNormalizer::new(suffixes);
+
+        let include: Vec<String> = if !self.include.is_empty() {
+            self.include.clone()
+        } else {
+            loaded.cfg.include.clone().unwrap_or_default()
+        };
+        // CLI globs are relative to the working directory, config and ignore-file globs to
+        // the config directory.
+        let mut excludes = if !self.exclude.is_empty() {
+            exclude_patterns(&self.exclude, None)?
+        } else {
+            exclude_patterns(&loaded.cfg.exclude.clone().unwrap_or_default(), base)?
+        };
+        let ignore_dir = match base {
+            Some(dir) => dir.to_path_buf(),
+            None => std::env::current_dir()?,
+        };
+        excludes.extend(exclude_patterns(
+            &read_ignore_file(&ignore_dir)?,
+            Some(&ignore_dir),
+        )?);
+        let resolver = ModuleResolver::new(paths.clone()).with_excludes(excludes.clone());
+        let includes: Vec<(String, PathBuf)> = expand_includes(&include, &paths, &normalizer)?
+            .into_iter()
+            .filter(|(_, path)| !resolver.is_excluded(path))
+            .collect();
+
+        Ok(Project {
+            loaded,
+            input: input.to_path_buf(),
+            code,
+            paths,
+            preludes,
+            replace_flags,
+            vendor_flags,
+            replaces,
+            vendors,
+            vendor_paths,
+            vendor_conflict,
+            vendor_shadow,
+            externals,
+            includes,
+            excludes,
+            normalizer,
+        })
+    }
+}
+
+impl Project {
+    /// Target dialect: `--lua`, else the config, else 5.1.
+    pub(crate) fn dialect(&self, flag: Option<&str>) -> Result<LuaDialect> {
+        let lua_ver: LuaDialect = flag
+            .or(self.loaded.cfg.lua.as_deref())
+            .unwrap_or("5.1")
+            .parse()?;
+
+        // Warn if selected dialect likely unsupported by this build
+        if let Some(feat) = lua_ver.required_feature() {
+            if !lua_ver.is_built() {
+                eprintln!(
+                    "warning: requested Lua dialect requires feature '{feat}', but this binary was built without it.\n         Rebuild with: cargo build --features {feat}"
+                );
+            }
+        }
+        Ok(lua_ver)
+    }
+
+    pub(crate) fn resolver(&self) -> ModuleResolver {
+        ModuleResolver::new(self.paths.clone()).with_excludes(self.excludes.clone())
+    }
+
+    pub(crate) fn graph(
+        &self,
+        resolver: &ModuleResolver,
+        cache: Option<&BuildCache>,
+    ) -> ModuleGraph {
+        ModuleGraph::build_with_roots(
+            &self.code,
+            &self.includes,
+            resolver,
+            &self.normalizer,
+            cache,
+        )
+    }
+
+    /// The graph `graph`, `why` and `lint` inspect: with `--vendor-shadow vendor`, first-party
+    /// modules named like a vendor module give way to it as in the bundle. Shadowing is never
+    /// an error here.
+    pub(crate) fn inspected_graph(
+        &self,
+        resolver: &ModuleResolver,
+        vendor_mods: &HashMap<String, PathBuf>,
+    ) -> Result<ModuleGraph> {
+        let mut graph = self.graph(resolver, None);
+        if self.vendor_shadow == VendorShadowPolicy::Vendor {
+            apply_vendor_shadows(&mut graph, vendor_mods, self.vendor_shadow)?;
+        }
+        Ok(graph)
+    }
+
+    /// Vendor modules by name, with the names found under more than one vendor root.
+    pub(crate) fn vendor_modules(&self) -> Result<VendorModules> {
+        collect_vendor_set(
+            &self.vendors,
+            &self.replaces,
+            &self.normalizer,
+            self.vendor_conflict,
+        )
+    }
+}
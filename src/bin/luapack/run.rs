@@ -0,0 +1,73 @@
+//! `luapack run`: bundle in memory and execute the result in the embedded Lua VM.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{ArgAction, Parser};
+
+use crate::BundleCmd;
+
+#[derive(Parser, Debug)]
+pub(crate) struct RunCmd {
+    #[command(flatten)]
+    bundle: BundleCmd,
+
+    /// Lua files run before the bundle to define host globals (e.g., a fake `Spring` table)
+    #[arg(long = "stubs", value_name = "FILE", action = ArgAction::Append)]
+    stubs: Vec<PathBuf>,
+
+    /// Script arguments after `--`, passed as `...` and the `arg` table
+    #[arg(value_name = "ARGS", last = true)]
+    args: Vec<String>,
+}
+
+#[cfg(feature = "run")]
+pub(crate) fn run(cmd: RunCmd) -> Result<()> {
+    use luapack::{load_config, resolve_pathbuf, run_bundle, vm_dialect, RunCtx};
+
+    if cmd.bundle.watch {
+        anyhow::bail!("--watch is not supported by `luapack run`");
+    }
+    let build = crate::build(&cmd.bundle, true, &[], None)?;
+    let Some((code, source_map)) = build.bundle else {
+        anyhow::bail!("no bundle was generated");
+    };
+
+    let stubs: Vec<PathBuf> = if !cmd.stubs.is_empty() {
+        cmd.stubs.clone()
+    } else {
+        let loaded = load_config(cmd.bundle.project.options.config.as_deref())?;
+        let base = loaded.dir.as_deref();
+        loaded
+            .cfg
+            .stubs
+            .unwrap_or_default()
+            .iter()
+            .map(|p| resolve_pathbuf(base, p))
+            .collect()
+    };
+
+    let vm = vm_dialect();
+    if build.dialect != vm {
+        eprintln!(
+            "warning: bundle targets Lua {}, but this binary runs Lua {vm}",
+            build.dialect
+        );
+    }
+
+    run_bundle(
+        &code,
+        &RunCtx {
+            args: &cmd.args,
+            stubs: &stubs,
+            source_map: &source_map,
+        },
+    )
+}
+
+#[cfg(not(feature = "run"))]
+pub(crate) fn run(_cmd: RunCmd) -> Result<()> {
+    anyhow::bail!(
+        "`luapack run` needs an embedded Lua VM, but this binary was built without one.\n       Rebuild with: cargo build --features run-lua54 (or run-lua51, run-lua52, run-lua53, run-luajit)"
+    )
+}
@@ -0,0 +1,63 @@
+//! `luapack unused`: list first-party files no entry requires.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{ArgAction, Parser};
+use luapack::{find_unused_modules, ModuleGraph};
+
+use crate::project::ProjectArgs;
+
+#[derive(Parser, Debug)]
+pub(crate) struct UnusedCmd {
+    #[command(flatten)]
+    project: ProjectArgs,
+
+    /// Extra entry files or globs whose requires count as used (tests, plugins loaded at runtime)
+    #[arg(long = "root", value_name = "FILE", action = ArgAction::Append)]
+    roots: Vec<String>,
+}
+
+pub(crate) fn run(cmd: UnusedCmd) -> Result<()> {
+    let project = cmd.project.resolve()?;
+    let resolver = project.resolver();
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for root in &cmd.roots {
+        let matched: Vec<PathBuf> = glob::glob(root)
+            .with_context(|| format!("bad --root pattern: {}", root))?
+            .flatten()
+            .collect();
+        if matched.is_empty() {
+            anyhow::bail!("--root {} matches no file", root);
+        }
+        roots.extend(matched);
+    }
+
+    let mut used: Vec<PathBuf> = vec![project.input.clone()];
+    used.extend(project.preludes.iter().cloned());
+    used.extend(project.graph(&resolver, None).first_party.into_values());
+    for root in roots {
+        let code = fs::read_to_string(&root)
+            .with_context(|| format!("failed to read root: {}", root.display()))?;
+        let graph = ModuleGraph::build_from_entry_code(&code, &resolver, &project.normalizer);
+        used.push(root);
+        used.extend(graph.first_party.into_values());
+    }
+
+    // Excluded files are kept out of the bundle on purpose.
+    let unused: Vec<_> = find_unused_modules(&project.paths, &project.normalizer, &used)
+        .into_iter()
+        .filter(|m| !resolver.is_excluded(&m.path))
+        .collect();
+    if unused.is_empty() {
+        eprintln!("no unused modules");
+        return Ok(());
+    }
+    for m in &unused {
+        println!("{}\t{}", m.name, m.path.display());
+    }
+    eprintln!("{} unused modules", unused.len());
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! `luapack verify`: statically check a generated bundle.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use luapack::{format_issues, verify_bundle, VerifyCtx};
+
+use crate::project::ProjectOptions;
+
+#[derive(Parser, Debug)]
+pub(crate) struct VerifyCmd {
+    /// Bundle to check (e.g., dist/app.lua)
+    #[arg(value_name = "BUNDLE")]
+    bundle: PathBuf,
+
+    /// Dialect the bundle must be valid for: 5.1, 5.2, 5.3, 5.4, luajit or luau
+    #[arg(long)]
+    lua: Option<String>,
+
+    #[command(flatten)]
+    project: ProjectOptions,
+}
+
+pub(crate) fn run(cmd: VerifyCmd) -> Result<()> {
+    let project = cmd.project.resolve(&cmd.bundle)?;
+    let dialect = project.dialect(cmd.lua.as_deref())?;
+    let report = verify_bundle(
+        &project.code,
+        &VerifyCtx {
+            dialect,
+            replaces: &project.replaces,
+            externals: &project.externals,
+            resolver: Some(&project.resolver()),
+            normalizer: &project.normalizer,
+        },
+    );
+    if !report.issues.is_empty() {
+        anyhow::bail!(
+            "{} problem(s) found:\n{}",
+            report.issues.len(),
+            format_issues(&cmd.bundle, &report.issues)
+        );
+    }
+    eprintln!(
+        "bundle ok: {} modules, {} aliases (lua={})",
+        report.modules.len(),
+        report.aliases.len(),
+        dialect
+    );
+    Ok(())
+}
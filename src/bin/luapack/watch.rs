@@ -0,0 +1,158 @@
+//! `luapack bundle --watch`: poll the build inputs and rebuild when they change.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+use luapack::{parse_vendor_specs, to_glob_and_root, BuildCache, IGNORE_FILE};
+
+use crate::{build, BuildInputs, BundleCmd};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Changes are collected until the inputs stay unchanged for this long.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+const CONFIG_CANDIDATES: [&str; 4] = [
+    "luapack.toml",
+    "luapack.yaml",
+    "luapack.yml",
+    "luapack.json",
+];
+
+/// Modification time of every watched file; `None` once a file is gone.
+type Snapshot = BTreeMap<PathBuf, Option<SystemTime>>;
+
+pub(crate) fn watch(cli: &BundleCmd) -> Result<()> {
+    // Without --cache, unchanged files are still reused between rebuilds, just not saved.
+    let mut cache = BuildCache::in_memory();
+    let mut inputs = rebuild(cli, &mut cache, None, &[]);
+    let mut last = snapshot(&inputs);
+    eprintln!("watching {} files for changes (Ctrl-C to stop)", last.len());
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let mut current = snapshot(&inputs);
+        if current == last {
+            continue;
+        }
+        loop {
+            thread::sleep(DEBOUNCE);
+            let next = snapshot(&inputs);
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+        eprintln!("changed: {}", describe_changes(&last, &current));
+        inputs = rebuild(
+            cli,
+            &mut cache,
+            Some(inputs),
+            &changed_files(&last, &current),
+        );
+        last = snapshot(&inputs);
+    }
+}
+
+/// Run one build and report the outcome. Errors are printed, not returned, and the
+/// previous inputs stay watched so fixing the broken file triggers the next build.
+fn rebuild(
+    cli: &BundleCmd,
+    cache: &mut BuildCache,
+    previous: Option<BuildInputs>,
+    changed: &[PathBuf],
+) -> BuildInputs {
+    let started = Instant::now();
+    match build(cli, false, changed, Some(cache)).map(|b| b.inputs) {
+        Ok(inputs) => {
+            eprintln!(
+                "bundle ok: {} inputs in {} ms",
+                inputs.files.len(),
+                started.elapsed().as_millis()
+            );
+            inputs
+        }
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            previous.unwrap_or_else(|| fallback_inputs(cli))
+        }
+    }
+}
+
+/// What to watch when no build has succeeded yet: the entry, the config and the CLI templates.
+fn fallback_inputs(cli: &BundleCmd) -> BuildInputs {
+    let project = &cli.project.options;
+    let config = match &project.config {
+        Some(p) => vec![p.clone()],
+        None => CONFIG_CANDIDATES.iter().map(PathBuf::from).collect(),
+    };
+    let vendor_templates = parse_vendor_specs(&project.vendor)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|v| v.paths);
+    BuildInputs {
+        files: std::iter::once(cli.project.input.clone())
+            .chain(project.preludes.iter().cloned())
+            .chain(config)
+            .chain(std::iter::once(PathBuf::from(IGNORE_FILE)))
+            .collect(),
+        templates: project
+            .paths
+            .iter()
+            .cloned()
+            .chain(vendor_templates)
+            .collect(),
+    }
+}
+
+/// Stat the known inputs plus every file currently matching a search template,
+/// so modules added under `--path`/`--vendor` roots trigger a rebuild too.
+fn snapshot(inputs: &BuildInputs) -> Snapshot {
+    let mut files: Vec<PathBuf> = inputs.files.clone();
+    for template in &inputs.templates {
+        if !template.contains('?') {
+            continue;
+        }
+        let (pattern, _, _) = to_glob_and_root(template);
+        if let Ok(paths) = glob::glob(&pattern) {
+            files.extend(paths.flatten());
+        }
+    }
+    files
+        .into_iter()
+        .map(|p| fs::canonicalize(&p).unwrap_or(p))
+        .map(|p| {
+            let mtime = fs::metadata(&p).and_then(|m| m.modified()).ok();
+            (p, mtime)
+        })
+        .collect()
+}
+
+/// Files added or modified between two snapshots.
+fn changed_files(before: &Snapshot, after: &Snapshot) -> Vec<PathBuf> {
+    after
+        .iter()
+        .filter(|(path, mtime)| mtime.is_some() && before.get(*path) != Some(*mtime))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+fn describe_changes(before: &Snapshot, after: &Snapshot) -> String {
+    let mut changed: Vec<String> = Vec::new();
+    for (path, mtime) in after {
+        match before.get(path) {
+            None if mtime.is_some() => changed.push(format!("+{}", path.display())),
+            Some(old) if old != mtime => changed.push(path.display().to_string()),
+            _ => {}
+        }
+    }
+    for path in before.keys().filter(|p| !after.contains_key(*p)) {
+        changed.push(format!("-{}", path.display()));
+    }
+    match changed.len() {
+        0 => "inputs".to_string(),
+        1..=3 => changed.join(", "),
+        n => format!("{} (+{} more)", changed[..3].join(", "), n - 3),
+    }
+}
@@ -0,0 +1,39 @@
+//! `luapack why`: explain how one module is included, resolved and rewritten.
+
+use anyhow::Result;
+use clap::Parser;
+use luapack::{explain_module, format_explanation, GraphExportCtx};
+
+use crate::project::ProjectArgs;
+
+#[derive(Parser, Debug)]
+pub(crate) struct WhyCmd {
+    #[command(flatten)]
+    project: ProjectArgs,
+
+    /// Module to explain, as written in `require` (e.g., core.util)
+    #[arg(value_name = "MODULE")]
+    module: String,
+}
+
+pub(crate) fn run(cmd: WhyCmd) -> Result<()> {
+    let project = cmd.project.resolve()?;
+    let resolver = project.resolver();
+    let vendor_mods = project.vendor_modules()?.modules;
+    let graph = project.inspected_graph(&resolver, &vendor_mods)?;
+    let explanation = explain_module(
+        &graph,
+        &GraphExportCtx {
+            entry_path: &project.input,
+            replaces: &project.replaces,
+            vendor_mods: &vendor_mods,
+            externals: &project.externals,
+            resolver: Some(&resolver),
+            normalizer: &project.normalizer,
+        },
+        &project.vendors,
+        &cmd.module,
+    );
+    print!("{}", format_explanation(&explanation));
+    Ok(())
+}
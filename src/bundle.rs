@@ -1,16 +1,30 @@
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use clap::ValueEnum;
+use full_moon::ast::Ast;
 use handlebars::Handlebars;
 
-use crate::graph::ModuleGraph;
+use crate::cache::{BuildCache, CachedChunk, ChunkKey};
+use crate::dialect::parse_lua;
+use crate::dialect::{format_issues, LuaDialect};
+use crate::downlevel::downlevel_ast;
+use crate::graph::{ModuleGraph, ModuleUnit, ROOT_MODULE};
+use crate::instrument::{instrument_coverage_ast, Instrument, COVERAGE_PROBE};
+use crate::normalize::normalize_module_name;
 use crate::options::NameNormalizer;
-use crate::replace::ReplaceRule;
+use crate::parallel::parallel_map;
+use crate::replace::{MatchKind, ReplaceRule};
 use crate::resolve::ModuleResolver;
-use crate::transform::transform_requires;
+use crate::sandbox::{Sandbox, SandboxMode};
+use crate::source_map::{ChunkSpan, SourceMap};
+use crate::strip_debug::strip_debug;
+use crate::strip_types::strip_types_ast;
+use crate::transform::transform_ast;
+use crate::validate::{validate_ast, validate_dialect};
 
 #[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
 pub enum BindRequire {
@@ -26,35 +40,328 @@ pub struct BundleCtx<'a> {
     pub entry_source: &'a str,
     pub entry_path: &'a Path,
     pub bind: BindRequire,
+    /// Log every `__B_REQUIRE` with its loader time and collect them in `__LUAPACK_PROFILE`.
+    pub runtime_trace: bool,
+    /// Expose the read-only `__LUAPACK` table (module list, load state, version, build id).
+    pub introspect: bool,
+    /// Dev runtime: embed module dependencies and the `__LUAPACK_HOT` reload API.
+    pub hot_reload: bool,
+    /// Run every module in its own environment that catches global writes.
+    pub sandbox: Option<&'a Sandbox>,
+    /// Compile runtime instrumentation into first-party modules and the entry.
+    pub instrument: Option<Instrument>,
     pub resolver: Option<&'a ModuleResolver>,
     pub redact_base: Option<PathBuf>,
     pub normalizer: &'a NameNormalizer,
+    pub dialect: LuaDialect,
+    pub strip_types: bool,
+    /// Remove `assert(...)` statements from first-party modules and the entry.
+    pub strip_asserts: bool,
+    /// Remove `--@debug begin` ... `--@debug end` regions from first-party modules and the entry.
+    pub strip_marked: bool,
+    pub downlevel: bool,
+    pub validate: bool,
+    /// Reuse transformed chunks of unchanged files (`--cache`).
+    pub cache: Option<&'a BuildCache>,
+}
+
+/// Counters collected while generating a bundle.
+#[derive(Debug, Clone, Default)]
+pub struct BundleStats {
+    pub rewrites: usize,
+    pub types_stripped: usize,
+    pub downlevel_rewrites: usize,
+    /// `assert` statements and `--@debug` regions removed.
+    pub debug_stripped: usize,
+    /// Vendor modules whose content is identical to an earlier one, with the module whose
+    /// loader they share.
+    pub vendor_shared: Vec<(String, String)>,
+    /// Bundle line ranges of every module, vendor module, entry and prelude.
+    pub source_map: SourceMap,
+}
+
+/// Counts the lines of a growing bundle without rescanning what was already counted.
+#[derive(Default)]
+struct LineTracker {
+    scanned: usize,
+    newlines: usize,
+}
+
+impl LineTracker {
+    /// Bundle line (from 1) the next text pushed onto `out` starts on.
+    fn next_line(&mut self, out: &str) -> usize {
+        self.newlines += out[self.scanned..].matches('\n').count();
+        self.scanned = out.len();
+        self.newlines + 1
+    }
 }
 
 pub fn lua_quote(s: &str) -> String {
     format!("'{}'", s.replace('\'', "\\'"))
 }
 
+/// Parameters of a module loader; sandboxed loaders take their environment as `_ENV`.
+fn loader_params(ctx: &BundleCtx) -> &'static str {
+    if ctx.sandbox.is_some() {
+        "require, _ENV"
+    } else {
+        "require"
+    }
+}
+
+/// `{ 'a', 'b' }`: a Lua array of strings.
+fn lua_list(items: &[String]) -> String {
+    if items.is_empty() {
+        return "{}".to_string();
+    }
+    let quoted: Vec<String> = items.iter().map(|s| lua_quote(s)).collect();
+    format!("{{ {} }}", quoted.join(", "))
+}
+
+/// Path an instrumented file is reported under: relative to `base` when inside it.
+fn coverage_path(base: Option<&PathBuf>, path: &Path) -> String {
+    let shown = base.and_then(|b| path.strip_prefix(b).ok()).unwrap_or(path);
+    shown.to_string_lossy().replace('\\', "/")
+}
+
+/// Coverage hooks of an instrumented chunk: the line registering its probed lines, and the
+/// local its probes call, appended to the loader's first line so chunk lines keep their numbers.
+fn coverage_hooks(path: &str, lines: &[usize]) -> (String, String) {
+    if lines.is_empty() {
+        return (String::new(), String::new());
+    }
+    let list: Vec<String> = lines.iter().map(usize::to_string).collect();
+    (
+        format!(
+            "__B_COV.register({}, {{ {} }})\n",
+            lua_quote(path),
+            list.join(", ")
+        ),
+        format!(
+            " local {} = __B_COV.probe({})",
+            COVERAGE_PROBE,
+            lua_quote(path)
+        ),
+    )
+}
+
+/// For every bundled module (and the entry), the bundled modules it requires, by
+/// registered name. Vendor modules are not scanned and have no entries.
+fn module_deps(graph: &ModuleGraph, bundled: &HashSet<&str>) -> BTreeMap<String, Vec<String>> {
+    let mut deps: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for edge in &graph.edges {
+        let to = graph.aliases.get(&edge.to).unwrap_or(&edge.to);
+        if !bundled.contains(edge.from.as_str()) || !bundled.contains(to.as_str()) {
+            continue;
+        }
+        let list = deps.entry(edge.from.clone()).or_default();
+        if !list.contains(to) {
+            list.push(to.clone());
+        }
+    }
+    for list in deps.values_mut() {
+        list.sort();
+    }
+    deps
+}
+
 #[derive(serde::Serialize)]
 struct HeaderCtx {
     global: bool,
+    aliases: bool,
+    trace: bool,
+    introspect: bool,
+    /// Lua-quoted `(name, path)` pairs for `__LUAPACK.source_path`.
+    sources: Vec<HeaderSource>,
+    hot_reload: bool,
+    deps: Vec<HeaderDeps>,
+    coverage: bool,
+    sandbox: bool,
+    sandbox_error: bool,
+    sandbox_allow: Vec<HeaderAllow>,
     version: String,
+    build_id: &'static str,
+}
+
+#[derive(serde::Serialize)]
+struct HeaderSource {
+    name: String,
+    path: String,
+}
+
+/// A `--sandbox-allow` rule as Lua source.
+#[derive(serde::Serialize)]
+struct HeaderAllow {
+    prefix: String,
+    globals: String,
 }
 
-fn render_header(bind: BindRequire) -> String {
+/// A module and its dependencies, as Lua source.
+#[derive(serde::Serialize)]
+struct HeaderDeps {
+    name: String,
+    deps: String,
+}
+
+/// Stands in for `__LUAPACK.build_id` until the rest of the bundle is known.
+const BUILD_ID_PLACEHOLDER: &str = "@LUAPACK_BUILD_ID@";
+
+fn render_header(
+    ctx: &BundleCtx,
+    aliases: bool,
+    sources: Vec<HeaderSource>,
+    deps: &BTreeMap<String, Vec<String>>,
+) -> String {
     // Handlebars template for the bundle header. Switches behavior based on `global`.
     let tpl = r#"-- luapack bundle v{{version}} auto-generated: DO NOT EDIT
 local __B_LOADED = {}
 local __B_MODULES = {}
+{{#if aliases}}
+local __B_ALIASES = {}
+{{/if}}
 local __B_REQ_TO_PASS
+{{#if trace}}
+
+-- Runtime trace: one entry per __B_REQUIRE call, in call order.
+__LUAPACK_PROFILE = {}
+local __B_CLOCK = os and os.clock or function() return 0 end
+local __B_LOADING = {}
+local function __B_TRACE(from, name, cached)
+  local entry = { from = from, name = name, cached = cached, time = 0 }
+  __LUAPACK_PROFILE[#__LUAPACK_PROFILE + 1] = entry
+  return entry
+end
+local function __B_TRACE_LOG(entry)
+  local log = __LUAPACK_TRACE or print
+  if log then
+    log(string.format('[luapack] %s -> %s: %s', entry.from or '-', entry.name,
+      entry.cached and 'cached' or string.format('%.3f ms', entry.time * 1000)))
+  end
+end
+{{/if}}
+{{#if coverage}}
+
+-- Coverage (--instrument coverage): line hits of every instrumented file, by source path.
+__LUAPACK_COVERAGE = { files = {} }
+local __B_COV = __LUAPACK_COVERAGE
+
+-- Declare the probed lines of a file; they report 0 hits until they run.
+function __B_COV.register(path, lines)
+  local hits = __B_COV.files[path] or {}
+  for _, line in ipairs(lines) do hits[line] = hits[line] or 0 end
+  __B_COV.files[path] = hits
+end
+
+-- The function the probes of a registered file call with their line.
+function __B_COV.probe(path)
+  local hits = __B_COV.files[path]
+  return function(line) hits[line] = hits[line] + 1 end
+end
+
+-- Line hits as an LCOV tracefile, also written to `path` when given.
+function __B_COV.lcov(path)
+  local paths, out = {}, {}
+  for file in pairs(__B_COV.files) do paths[#paths + 1] = file end
+  table.sort(paths)
+  for _, file in ipairs(paths) do
+    local hits, lines, hit = __B_COV.files[file], {}, 0
+    for line in pairs(hits) do lines[#lines + 1] = line end
+    table.sort(lines)
+    out[#out + 1] = 'TN:'
+    out[#out + 1] = 'SF:' .. file
+    for _, line in ipairs(lines) do
+      out[#out + 1] = 'DA:' .. line .. ',' .. hits[line]
+      if hits[line] > 0 then hit = hit + 1 end
+    end
+    out[#out + 1] = 'LF:' .. #lines
+    out[#out + 1] = 'LH:' .. hit
+    out[#out + 1] = 'end_of_record'
+  end
+  local report = #out > 0 and table.concat(out, '\n') .. '\n' or ''
+  if path then
+    local file = assert(io.open(path, 'w'))
+    file:write(report)
+    file:close()
+  end
+  return report
+end
+{{/if}}
+{{#if sandbox}}
+
+-- Sandbox (--sandbox): each module gets an environment that reads through to _G and
+-- catches global assignments, unless an allow rule for its name prefix lists the global.
+local __B_G = _G
+local __B_SETFENV = setfenv
+local __B_SANDBOX_ALLOW = {
+{{#each sandbox_allow}}
+  { prefix = {{prefix}}, globals = {{globals}} },
+{{/each}}
+}
+local function __B_ENV(name)
+  local allowed = {}
+  for _, rule in ipairs(__B_SANDBOX_ALLOW) do
+    if name:sub(1, #rule.prefix) == rule.prefix then
+      for _, global in ipairs(rule.globals) do allowed[global] = true end
+    end
+  end
+{{#unless sandbox_error}}
+  local warned = {}
+{{/unless}}
+  return setmetatable({}, {
+    __index = __B_G,
+    __newindex = function(_, key, value)
+{{#if sandbox_error}}
+      if not allowed[key] then
+        error(string.format("module '%s' assigns global '%s'", name, tostring(key)), 2)
+      end
+{{else}}
+      if not allowed[key] and not warned[key] then
+        warned[key] = true
+        local warn = __LUAPACK_WARN or print
+        if warn then
+          warn(string.format("[luapack] module '%s' assigns global '%s'", name, tostring(key)))
+        end
+      end
+{{/if}}
+      __B_G[key] = value
+    end,
+  })
+end
+{{/if}}
 
 local function __B_REQUIRE(name)
+{{#if aliases}}
+  name = __B_ALIASES[name] or name
+{{/if}}
+{{#if trace}}
+  local from = __B_LOADING[#__B_LOADING]
+{{/if}}
   if __B_LOADED[name] ~= nil then
+{{#if trace}}
+    __B_TRACE_LOG(__B_TRACE(from, name, true))
+{{/if}}
     return __B_LOADED[name] == true and nil or __B_LOADED[name]
   end
   local loader = __B_MODULES[name]
   if loader then
+{{#if trace}}
+    local entry = __B_TRACE(from, name, false)
+    __B_LOADING[#__B_LOADING + 1] = name
+    local start = __B_CLOCK()
+{{/if}}
+{{#if sandbox}}
+    -- Lua 5.1 and LuaJIT swap the loader's environment; 5.2+ pass it as `_ENV`.
+    local env = __B_ENV(name)
+    if __B_SETFENV then __B_SETFENV(loader, env) end
+    local res = loader(__B_REQ_TO_PASS, env)
+{{else}}
     local res = loader(__B_REQ_TO_PASS)
+{{/if}}
+{{#if trace}}
+    entry.time = __B_CLOCK() - start
+    __B_LOADING[#__B_LOADING] = nil
+    __B_TRACE_LOG(entry)
+{{/if}}
     __B_LOADED[name] = (res == nil) and true or res
     return res
   end
@@ -76,24 +383,387 @@ end)()
 {{else}}
 __B_REQ_TO_PASS = __B_REQUIRE
 {{/if}}
+{{#if introspect}}
+
+-- Introspection API: read-only view of the bundle runtime.
+local __B_SOURCES = {
+{{#each sources}}
+  [{{name}}] = {{path}},
+{{/each}}
+}
+local function __B_NAME(name)
+{{#if aliases}}
+  return __B_ALIASES[name] or name
+{{else}}
+  return name
+{{/if}}
+end
+__LUAPACK = setmetatable({}, {
+  __index = {
+    version = '{{version}}',
+    build_id = '{{build_id}}',
+    modules = function()
+      local names = {}
+      for name in pairs(__B_MODULES) do
+        if name ~= '__root' then names[#names + 1] = name end
+      end
+      table.sort(names)
+      return names
+    end,
+    loaded = function(name)
+      return __B_LOADED[__B_NAME(name)] ~= nil
+    end,
+    unload = function(name)
+      name = __B_NAME(name)
+      local was = __B_LOADED[name] ~= nil
+      __B_LOADED[name] = nil
+      return was
+    end,
+    source_path = function(name)
+      return __B_SOURCES[__B_NAME(name)]
+    end,
+  },
+  __newindex = function() error('__LUAPACK is read-only', 2) end,
+  __metatable = false,
+})
+{{/if}}
+{{#if hot_reload}}
+
+-- Dev runtime (--hot-reload): bundled modules each module requires, and the reload API.
+local __B_DEPS = {
+{{#each deps}}
+  [{{name}}] = {{deps}},
+{{/each}}
+}
+__LUAPACK_HOT = {}
+
+-- Forget the cached value of a module; the next require runs its loader again.
+function __LUAPACK_HOT.invalidate(name)
+{{#if aliases}}
+  name = __B_ALIASES[name] or name
+{{/if}}
+  __B_LOADED[name] = nil
+end
+
+-- Replace the loader and dependency list of a module (used by hot update bundles).
+function __LUAPACK_HOT.register(name, deps, loader)
+  __B_MODULES[name] = loader
+  __B_DEPS[name] = deps
+end
+
+-- `names` and every module that requires one of them, directly or indirectly.
+function __LUAPACK_HOT.dependents(names)
+  local users = {}
+  for from, deps in pairs(__B_DEPS) do
+    for _, to in ipairs(deps) do
+      users[to] = users[to] or {}
+      users[to][#users[to] + 1] = from
+    end
+  end
+  local found, queue = {}, {}
+  for _, name in ipairs(names) do
+{{#if aliases}}
+    name = __B_ALIASES[name] or name
+{{/if}}
+    if not found[name] then
+      found[name] = true
+      queue[#queue + 1] = name
+    end
+  end
+  local i = 1
+  while queue[i] do
+    for _, user in ipairs(users[queue[i]] or {}) do
+      if not found[user] then
+        found[user] = true
+        queue[#queue + 1] = user
+      end
+    end
+    i = i + 1
+  end
+  local list = {}
+  for name in pairs(found) do list[#list + 1] = name end
+  table.sort(list)
+  return list
+end
+
+-- Invalidate `names` and their dependents, then re-require the ones that were loaded,
+-- dependencies first. The entry module is invalidated but never re-run.
+function __LUAPACK_HOT.reload(names)
+  local affected, order, seen = {}, {}, {}
+  for _, name in ipairs(__LUAPACK_HOT.dependents(names)) do affected[name] = true end
+  local function visit(name)
+    if seen[name] then return end
+    seen[name] = true
+    for _, dep in ipairs(__B_DEPS[name] or {}) do
+      if affected[dep] then visit(dep) end
+    end
+    order[#order + 1] = name
+  end
+  for _, name in ipairs(__LUAPACK_HOT.dependents(names)) do visit(name) end
+  local was_loaded = {}
+  for _, name in ipairs(order) do
+    was_loaded[name] = __B_LOADED[name] ~= nil
+    __B_LOADED[name] = nil
+  end
+  local reloaded = {}
+  for _, name in ipairs(order) do
+    if was_loaded[name] and name ~= '__root' then
+      __B_REQUIRE(name)
+      reloaded[#reloaded + 1] = name
+    end
+  end
+  return reloaded
+end
+{{/if}}
 
 "#;
     let mut hbs = Handlebars::new();
+    hbs.register_escape_fn(handlebars::no_escape);
     let _ = hbs.register_template_string("header", tpl);
-    let ctx = HeaderCtx {
-        global: matches!(bind, BindRequire::Global),
+    let hctx = HeaderCtx {
+        global: matches!(ctx.bind, BindRequire::Global),
+        aliases,
+        trace: ctx.runtime_trace,
+        introspect: ctx.introspect,
+        sources,
+        hot_reload: ctx.hot_reload,
+        coverage: ctx.instrument == Some(Instrument::Coverage),
+        sandbox: ctx.sandbox.is_some(),
+        sandbox_error: ctx.sandbox.is_some_and(|s| s.mode == SandboxMode::Error),
+        sandbox_allow: ctx
+            .sandbox
+            .map(|s| s.allow.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| HeaderAllow {
+                prefix: lua_quote(&rule.prefix),
+                globals: lua_list(&rule.globals),
+            })
+            .collect(),
+        deps: deps
+            .iter()
+            .map(|(name, deps)| HeaderDeps {
+                name: lua_quote(name),
+                deps: lua_list(deps),
+            })
+            .collect(),
         version: env!("CARGO_PKG_VERSION").to_string(),
+        build_id: BUILD_ID_PLACEHOLDER,
     };
-    hbs.render("header", &ctx)
+    hbs.render("header", &hctx)
         .unwrap_or_else(|_| tpl.to_string())
 }
 
-pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, usize)> {
+/// One chunk to emit: its source and, for graph modules, the AST parsed while scanning.
+struct ChunkJob<'a> {
+    path: &'a Path,
+    source: Option<String>,
+    ast: Option<Ast>,
+    transform: bool,
+}
+
+/// Result of [`process_chunk`]; `code` is `None` when the file could not be read.
+#[derive(Default)]
+struct ChunkOutput {
+    code: Option<String>,
+    stats: BundleStats,
+    errors: Vec<String>,
+    /// Lines probed by `--instrument coverage`.
+    coverage_lines: Vec<usize>,
+}
+
+impl<'a> ChunkJob<'a> {
+    fn from_file(path: &'a Path, transform: bool) -> Self {
+        Self {
+            path,
+            source: None,
+            ast: None,
+            transform,
+        }
+    }
+
+    fn from_unit(unit: &'a ModuleUnit, transform: bool) -> Self {
+        Self {
+            path: &unit.path,
+            source: Some(unit.source.clone()),
+            ast: unit.ast.clone(),
+            transform,
+        }
+    }
+}
+
+/// Files the `match=path` replace rules see for the literal requires of `source`, one
+/// `module -> path` per line; empty when no such rule is given.
+fn path_rule_inputs(cache: &BuildCache, path: &Path, source: &str, ctx: &BundleCtx) -> String {
+    let Some(resolver) = ctx.resolver else {
+        return String::new();
+    };
+    if !ctx.replaces.iter().any(|r| r.match_kind == MatchKind::Path) {
+        return String::new();
+    }
     let mut out = String::new();
-    let mut total_rewrites = 0usize;
+    for r in cache.requires(path, source) {
+        let name = normalize_module_name(&r.module, ctx.normalizer);
+        let found = resolver.find(&name).map(|(_, p)| p);
+        out.push_str(&format!(
+            "{} -> {}\n",
+            name,
+            found
+                .as_deref()
+                .map_or(Cow::Borrowed("-"), Path::to_string_lossy)
+        ));
+    }
+    out
+}
+
+/// Transform, strip Luau types and downlevel (as enabled), then validate one chunk against
+/// the target dialect. The chunk is parsed at most once; cached output is reused as is.
+fn process_chunk(job: ChunkJob, ctx: &BundleCtx) -> ChunkOutput {
+    let mut out = ChunkOutput::default();
+    let source = match job.source {
+        Some(s) => s,
+        None => match fs::read_to_string(job.path) {
+            Ok(s) => s,
+            Err(_) => return out,
+        },
+    };
+    let path = job.path;
+    let mut ast = job.ast;
+    let mut build = |errors: &mut Vec<String>| {
+        let mut chunk = CachedChunk::default();
+        // Debug code goes first, as text, so nothing that follows sees it.
+        let mut source = Cow::Borrowed(source.as_str());
+        if job.transform && (ctx.strip_asserts || ctx.strip_marked) {
+            match strip_debug(&source, ctx.strip_asserts, ctx.strip_marked) {
+                Ok((code, n)) if n > 0 => {
+                    chunk.debug_stripped = n;
+                    source = Cow::Owned(code);
+                    ast = None;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    errors.push(format!("{}: {:#}", path.display(), e));
+                    chunk.code = source.into_owned();
+                    return chunk;
+                }
+            }
+        }
+        let ast = match ast.take().map(Ok).unwrap_or_else(|| parse_lua(&source)) {
+            Ok(ast) => ast,
+            Err(_) => {
+                // Unparsable chunks pass through; validation reports the syntax errors.
+                if ctx.validate {
+                    let issues = validate_dialect(&source, ctx.dialect);
+                    if !issues.is_empty() {
+                        errors.push(format_issues(path, &issues));
+                    }
+                }
+                chunk.code = source.into_owned();
+                return chunk;
+            }
+        };
+        // First-party chunks (the transformed ones) are instrumented, before any rewrite
+        // moves their tokens.
+        let ast = if job.transform && ctx.instrument == Some(Instrument::Coverage) {
+            let (ast, lines) = instrument_coverage_ast(ast);
+            chunk.coverage_lines = lines;
+            ast
+        } else {
+            ast
+        };
+        let (ast, rewrites) = if job.transform {
+            transform_ast(ast, ctx.replaces, Some(path), ctx.resolver, ctx.normalizer)
+        } else {
+            (ast, 0)
+        };
+        chunk.rewrites = rewrites;
+        let ast = if ctx.strip_types {
+            let (ast, n) = strip_types_ast(ast);
+            chunk.types_stripped = n;
+            ast
+        } else {
+            ast
+        };
+        let ast = if ctx.downlevel {
+            match downlevel_ast(ast, ctx.dialect) {
+                Ok((ast, n)) => {
+                    chunk.downlevel_rewrites = n;
+                    ast
+                }
+                Err(issues) => {
+                    errors.push(format_issues(path, &issues));
+                    chunk.code = source.into_owned();
+                    return chunk;
+                }
+            }
+        } else {
+            ast
+        };
+        chunk.code = ast.to_string();
+        if ctx.validate {
+            let issues = validate_ast(&ast, &chunk.code, ctx.dialect);
+            if !issues.is_empty() {
+                errors.push(format_issues(path, &issues));
+            }
+        }
+        chunk
+    };
+    let chunk = match ctx.cache {
+        Some(cache) => {
+            let key = ChunkKey {
+                transform: job.transform,
+                resolved: if job.transform {
+                    path_rule_inputs(cache, path, &source, ctx)
+                } else {
+                    String::new()
+                },
+            };
+            let mut failed: Option<CachedChunk> = None;
+            cache
+                .chunk(path, &source, &key, || {
+                    let chunk = build(&mut out.errors);
+                    if !out.errors.is_empty() {
+                        failed = Some(chunk);
+                        return None;
+                    }
+                    Some(chunk)
+                })
+                .or(failed)
+                .unwrap_or_default()
+        }
+        None => build(&mut out.errors),
+    };
+    out.stats.rewrites = chunk.rewrites;
+    out.stats.types_stripped = chunk.types_stripped;
+    out.stats.downlevel_rewrites = chunk.downlevel_rewrites;
+    out.stats.debug_stripped = chunk.debug_stripped;
+    out.coverage_lines = chunk.coverage_lines;
+    out.code = Some(chunk.code);
+    out
+}
 
-    let header = render_header(ctx.bind);
-    out.push_str(&header);
+pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, usize)> {
+    let (out, stats) = generate_bundle_with_stats(graph, ctx)?;
+    Ok((out, stats.rewrites))
+}
+
+pub fn generate_bundle_with_stats(
+    graph: &ModuleGraph,
+    ctx: BundleCtx,
+) -> Result<(String, BundleStats)> {
+    let mut out = String::new();
+    let mut stats = BundleStats::default();
+    let mut errors: Vec<String> = Vec::new();
+    let mut spans: Vec<ChunkSpan> = Vec::new();
+    let mut lines = LineTracker::default();
+    let mut span = |out: &str, module: Option<&str>, path: &Path, code: &str| {
+        spans.push(ChunkSpan {
+            module: module.map(str::to_string),
+            path: path.to_path_buf(),
+            first_line: lines.next_line(out),
+            line_count: code.lines().count(),
+        });
+    };
 
     // Base directory for redacting absolute paths
     let base = ctx
@@ -103,31 +773,106 @@ pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, u
 
     let mut mods: Vec<_> = graph.first_party.iter().collect();
     mods.sort_by(|a, b| a.0.cmp(b.0));
-    let mut emitted: HashSet<String> = HashSet::new();
+    let emitted: HashSet<&String> = mods
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(graph.aliases.keys())
+        .collect();
+    let mut vmods: Vec<_> = ctx
+        .vendor_mods
+        .iter()
+        .filter(|(name, _)| !emitted.contains(name))
+        .collect();
+    vmods.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut sources: Vec<HeaderSource> = Vec::new();
+    if ctx.introspect {
+        let root = ("__root".to_string(), ctx.entry_path.to_path_buf());
+        let named = mods
+            .iter()
+            .chain(vmods.iter())
+            .map(|(n, p)| ((*n).clone(), (*p).clone()));
+        for (name, path) in named.chain(std::iter::once(root)) {
+            // Like the module comments, only paths under the redaction base are shown.
+            if let Some(rel) = base.as_ref().and_then(|c| path.strip_prefix(c).ok()) {
+                sources.push(HeaderSource {
+                    name: lua_quote(&name),
+                    path: lua_quote(&rel.to_string_lossy().replace('\\', "/")),
+                });
+            }
+        }
+    }
+    let deps = if ctx.hot_reload {
+        let bundled: HashSet<&str> = mods
+            .iter()
+            .chain(vmods.iter())
+            .map(|(n, _)| n.as_str())
+            .chain(std::iter::once(ROOT_MODULE))
+            .collect();
+        module_deps(graph, &bundled)
+    } else {
+        BTreeMap::new()
+    };
+    out.push_str(&render_header(
+        &ctx,
+        !graph.aliases.is_empty(),
+        sources,
+        &deps,
+    ));
+    let header_len = out.len();
+
+    // Every chunk is independent: process them all in parallel, then emit in order.
+    let mut jobs: Vec<ChunkJob> = Vec::new();
+    for (_, path) in &mods {
+        jobs.push(match graph.units.get(*path) {
+            Some(unit) => ChunkJob::from_unit(unit, true),
+            None => ChunkJob::from_file(path, true),
+        });
+    }
+    for (_, path) in &vmods {
+        jobs.push(ChunkJob::from_file(path, false));
+    }
+    jobs.push(ChunkJob {
+        path: ctx.entry_path,
+        source: Some(ctx.entry_source.to_string()),
+        ast: graph
+            .entry
+            .as_ref()
+            .filter(|e| e.source == ctx.entry_source)
+            .and_then(|e| e.ast.clone()),
+        transform: true,
+    });
+    for p in ctx.preludes {
+        jobs.push(ChunkJob::from_file(p, false));
+    }
+    let mut results = parallel_map(jobs, |job| process_chunk(job, &ctx)).into_iter();
+    let mut next_chunk = || {
+        let r = results.next().unwrap_or_default();
+        stats.rewrites += r.stats.rewrites;
+        stats.types_stripped += r.stats.types_stripped;
+        stats.downlevel_rewrites += r.stats.downlevel_rewrites;
+        stats.debug_stripped += r.stats.debug_stripped;
+        errors.extend(r.errors);
+        (r.code, r.coverage_lines)
+    };
+
     for (name, path) in mods {
-        emitted.insert(name.clone());
+        let (code, probed) = next_chunk();
+        let (register, probe) = coverage_hooks(&coverage_path(base.as_ref(), path), &probed);
         let rel = base.as_ref().and_then(|c| path.strip_prefix(c).ok());
         match rel {
             Some(rp) => out.push_str(&format!("-- module: {}  (from {})\n", name, rp.display())),
             None => out.push_str(&format!("-- module: {}\n", name)),
         }
+        out.push_str(&register);
         out.push_str(&format!(
-            "__B_MODULES[{}] = function(require)\n",
-            lua_quote(name)
+            "__B_MODULES[{}] = function({}){}\n",
+            lua_quote(name),
+            loader_params(&ctx),
+            probe
         ));
-        if let Ok(code) = fs::read_to_string(path) {
-            let (code, c) = if !ctx.replaces.is_empty() {
-                transform_requires(
-                    &code,
-                    ctx.replaces,
-                    Some(path.as_path()),
-                    ctx.resolver,
-                    ctx.normalizer,
-                )
-            } else {
-                (code, 0)
-            };
-            total_rewrites += c;
+        if let Some(code) = code {
+            span(&out, Some(name), path, &code);
             out.push_str(&code);
             if !code.ends_with('\n') {
                 out.push('\n');
@@ -136,12 +881,10 @@ pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, u
         out.push_str("end\n\n");
     }
 
-    let mut vmods: Vec<_> = ctx.vendor_mods.iter().collect();
-    vmods.sort_by(|a, b| a.0.cmp(b.0));
+    // Identical vendor files under different names share one loader (not one cache entry).
+    let mut loaders: HashMap<blake3::Hash, &String> = HashMap::new();
     for (name, path) in vmods {
-        if emitted.contains(name) {
-            continue;
-        }
+        let code = next_chunk().0;
         let rel = base.as_ref().and_then(|c| path.strip_prefix(c).ok());
         match rel {
             Some(rp) => out.push_str(&format!(
@@ -151,11 +894,28 @@ pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, u
             )),
             None => out.push_str(&format!("-- vendor module: {}\n", name)),
         }
+        let hash = code.as_ref().map(|c| blake3::hash(c.as_bytes()));
+        if let Some(first) = hash.and_then(|h| loaders.get(&h)) {
+            out.push_str(&format!(
+                "__B_MODULES[{}] = __B_MODULES[{}]\n\n",
+                lua_quote(name),
+                lua_quote(first)
+            ));
+            stats
+                .vendor_shared
+                .push((name.to_string(), first.to_string()));
+            continue;
+        }
+        if let Some(hash) = hash {
+            loaders.insert(hash, name);
+        }
         out.push_str(&format!(
-            "__B_MODULES[{}] = function(require)\n",
-            lua_quote(name)
+            "__B_MODULES[{}] = function({})\n",
+            lua_quote(name),
+            loader_params(&ctx)
         ));
-        if let Ok(code) = fs::read_to_string(path) {
+        if let Some(code) = code {
+            span(&out, Some(name), path, &code);
             out.push_str(&code);
             if !code.ends_with('\n') {
                 out.push('\n');
@@ -164,20 +924,32 @@ pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, u
         out.push_str("end\n\n");
     }
 
+    if !graph.aliases.is_empty() {
+        // Other names of already bundled files share their loader and cache entry.
+        let mut aliases: Vec<_> = graph.aliases.iter().collect();
+        aliases.sort();
+        out.push_str("-- module aliases\n");
+        for (alias, name) in aliases {
+            out.push_str(&format!(
+                "__B_ALIASES[{}] = {}\n",
+                lua_quote(alias),
+                lua_quote(name)
+            ));
+        }
+        out.push('\n');
+    }
+
+    let (entry_src, probed) = next_chunk();
+    let entry_src = entry_src.unwrap_or_default();
+    let (register, probe) = coverage_hooks(&coverage_path(base.as_ref(), ctx.entry_path), &probed);
     out.push_str("-- root module: __root\n");
-    out.push_str("__B_MODULES['__root'] = function(require)\n");
-    let (entry_src, entry_c) = if !ctx.replaces.is_empty() {
-        transform_requires(
-            ctx.entry_source,
-            ctx.replaces,
-            Some(ctx.entry_path),
-            ctx.resolver,
-            ctx.normalizer,
-        )
-    } else {
-        (ctx.entry_source.to_string(), 0)
-    };
-    total_rewrites += entry_c;
+    out.push_str(&register);
+    out.push_str(&format!(
+        "__B_MODULES['__root'] = function({}){}\n",
+        loader_params(&ctx),
+        probe
+    ));
+    span(&out, Some("__root"), ctx.entry_path, &entry_src);
     out.push_str(&entry_src);
     if !ctx.entry_source.ends_with('\n') {
         out.push('\n');
@@ -185,7 +957,7 @@ pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, u
     out.push_str("end\n\n");
 
     for p in ctx.preludes {
-        if let Ok(txt) = fs::read_to_string(p) {
+        if let Some(txt) = next_chunk().0 {
             let rel = base.as_ref().and_then(|c| p.strip_prefix(c).ok());
             match rel {
                 Some(rp) => {
@@ -197,6 +969,7 @@ pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, u
                     out.push_str("-- prelude\n");
                 }
             }
+            span(&out, None, p, &txt);
             out.push_str(&txt);
             if !txt.ends_with('\n') {
                 out.push('\n');
@@ -210,5 +983,110 @@ pub fn generate_bundle(graph: &ModuleGraph, ctx: BundleCtx) -> Result<(String, u
         None => out.push_str("return __B_REQUIRE('__root')\n"),
     }
 
-    Ok((out, total_rewrites))
+    if !errors.is_empty() {
+        anyhow::bail!(
+            "source is not valid for Lua {}:\n{}",
+            ctx.dialect,
+            errors.join("\n")
+        );
+    }
+
+    if ctx.introspect {
+        // The build id covers everything after the header, so it changes with any module.
+        let hash = blake3::hash(&out.as_bytes()[header_len..]).to_hex();
+        out = out.replacen(BUILD_ID_PLACEHOLDER, &hash[..16], 1);
+    }
+
+    stats.source_map = SourceMap { chunks: spans };
+    Ok((out, stats))
+}
+
+/// Generate a hot update for a bundle built with `hot_reload`: a chunk that, run in the
+/// same Lua state, registers new loaders for `modules` and reloads them and their dependents.
+pub fn generate_hot_update(
+    graph: &ModuleGraph,
+    ctx: &BundleCtx,
+    modules: &[String],
+) -> Result<String> {
+    let mut names: Vec<&String> = Vec::new();
+    let mut jobs: Vec<ChunkJob> = Vec::new();
+    for module in modules {
+        let name = graph.aliases.get(module).unwrap_or(module);
+        if names.contains(&name) {
+            continue;
+        }
+        let job = if let Some(path) = graph.first_party.get(name) {
+            match graph.units.get(path) {
+                Some(unit) => ChunkJob::from_unit(unit, true),
+                None => ChunkJob::from_file(path, true),
+            }
+        } else if let Some(path) = ctx.vendor_mods.get(name) {
+            ChunkJob::from_file(path, false)
+        } else {
+            anyhow::bail!("module '{}' is not part of the bundle", module);
+        };
+        names.push(name);
+        jobs.push(job);
+    }
+
+    let bundled: HashSet<&str> = graph
+        .first_party
+        .keys()
+        .chain(ctx.vendor_mods.keys())
+        .map(String::as_str)
+        .chain(std::iter::once(ROOT_MODULE))
+        .collect();
+    let deps = module_deps(graph, &bundled);
+    let base = ctx
+        .redact_base
+        .clone()
+        .or_else(|| std::env::current_dir().ok());
+
+    let mut out = format!(
+        "-- luapack hot update v{} auto-generated: DO NOT EDIT\nlocal __B_HOT = __LUAPACK_HOT\nif not __B_HOT then\n  error('luapack hot update: the running bundle was not built with --hot-reload')\nend\n\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    if ctx.instrument == Some(Instrument::Coverage) {
+        out.push_str("local __B_COV = __LUAPACK_COVERAGE\nif not __B_COV then\n  error('luapack hot update: the running bundle was not built with --instrument coverage')\nend\n\n");
+    }
+    let mut errors: Vec<String> = Vec::new();
+    let paths: Vec<&Path> = jobs.iter().map(|j| j.path).collect();
+    let results = parallel_map(jobs, |job| process_chunk(job, ctx));
+    for ((name, path), r) in names.iter().zip(paths).zip(results) {
+        errors.extend(r.errors);
+        let (register, probe) =
+            coverage_hooks(&coverage_path(base.as_ref(), path), &r.coverage_lines);
+        let rel = base.as_ref().and_then(|c| path.strip_prefix(c).ok());
+        match rel {
+            Some(rp) => out.push_str(&format!("-- module: {}  (from {})\n", name, rp.display())),
+            None => out.push_str(&format!("-- module: {}\n", name)),
+        }
+        let module_deps = deps.get(*name).map(Vec::as_slice).unwrap_or_default();
+        out.push_str(&register);
+        out.push_str(&format!(
+            "__B_HOT.register({}, {}, function({}){}\n",
+            lua_quote(name),
+            lua_list(module_deps),
+            loader_params(ctx),
+            probe
+        ));
+        if let Some(code) = r.code {
+            out.push_str(&code);
+            if !code.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+        out.push_str("end)\n\n");
+    }
+    let names: Vec<String> = names.into_iter().cloned().collect();
+    out.push_str(&format!("return __B_HOT.reload({})\n", lua_list(&names)));
+
+    if !errors.is_empty() {
+        anyhow::bail!(
+            "source is not valid for Lua {}:\n{}",
+            ctx.dialect,
+            errors.join("\n")
+        );
+    }
+    Ok(out)
 }
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::options::BundleOptions;
+use crate::scan::{find_literal_requires, RequireMatch};
+
+const CACHE_FILE: &str = "cache.json";
+/// Bumped whenever the layout of cache entries changes.
+const CACHE_FORMAT: u32 = 2;
+
+/// Output of transforming and preparing one chunk, as stored in the cache.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachedChunk {
+    pub code: String,
+    pub rewrites: usize,
+    pub types_stripped: usize,
+    pub downlevel_rewrites: usize,
+    pub debug_stripped: usize,
+    /// Lines that got a coverage probe (`--instrument coverage`).
+    pub coverage_lines: Vec<usize>,
+}
+
+/// What a cached chunk depends on besides the content of its file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkKey {
+    /// Rewritten by the replace rules (first-party modules and the entry); vendor modules
+    /// and preludes are not.
+    pub transform: bool,
+    /// Files the `match=path` replace rules resolve the chunk's requires to. They change
+    /// with the filesystem rather than with the chunk; empty without such rules.
+    pub resolved: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheEntry {
+    hash: String,
+    requires: Option<Vec<RequireMatch>>,
+    /// Transformed chunk, with the hash of the [`ChunkKey::resolved`] it was built with.
+    chunk: Option<CachedChunk>,
+    #[serde(default)]
+    chunk_resolved: String,
+    /// The same file prepared without the replace rules.
+    #[serde(default)]
+    plain_chunk: Option<CachedChunk>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    fingerprint: String,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+/// On-disk cache of per-file scan and transform results (`--cache`).
+///
+/// Entries are keyed by file path and reused only while the file's content hash matches.
+/// The whole cache is dropped when its fingerprint (luapack version, replace rules,
+/// normalizer suffixes, search paths and dialect settings) differs from the current build.
+pub struct BuildCache {
+    /// `None` for a cache that only lives in memory (see [`BuildCache::in_memory`]).
+    dir: Option<PathBuf>,
+    fingerprint: String,
+    entries: Mutex<HashMap<PathBuf, CacheEntry>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl BuildCache {
+    /// Load the cache stored in `dir`, or start empty if it is missing, unreadable or stale.
+    pub fn open(dir: &Path, fingerprint: &str) -> Self {
+        let entries = fs::read_to_string(dir.join(CACHE_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str::<CacheFile>(&s).ok())
+            .filter(|f| f.fingerprint == fingerprint)
+            .map(|f| f.entries)
+            .unwrap_or_default();
+        Self {
+            dir: Some(dir.to_path_buf()),
+            fingerprint: fingerprint.to_string(),
+            entries: Mutex::new(entries),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// An empty cache that is never written to disk, for a process that runs several builds.
+    /// Call [`BuildCache::begin_build`] before each build.
+    pub fn in_memory() -> Self {
+        Self {
+            dir: None,
+            fingerprint: String::new(),
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Prepare for the next build: reset the hit counters and drop every entry when the
+    /// fingerprint differs from the previous build's.
+    pub fn begin_build(&mut self, fingerprint: &str) {
+        if self.fingerprint != fingerprint {
+            self.fingerprint = fingerprint.to_string();
+            self.entries
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+        *self.hits.get_mut() = 0;
+        *self.misses.get_mut() = 0;
+    }
+
+    /// Fingerprint of every option that affects cached results.
+    pub fn fingerprint(opts: &BundleOptions) -> String {
+        let mut suffixes = opts.normalizer.suffixes();
+        suffixes.sort();
+        let key = format!(
+            "luapack {} cache format {}\nreplaces={:?}\nsuffixes={:?}\npaths={:?}\nlua={} strip_types={} downlevel={} validate={} instrument={:?} strip_asserts={} strip_marked={}",
+            env!("CARGO_PKG_VERSION"),
+            CACHE_FORMAT,
+            opts.replaces,
+            suffixes,
+            opts.paths,
+            opts.lua,
+            opts.strip_types,
+            opts.downlevel,
+            opts.validate,
+            opts.instrument,
+            opts.strip_asserts,
+            opts.strip_marked,
+        );
+        blake3::hash(key.as_bytes()).to_hex().to_string()
+    }
+
+    /// Literal requires of `code`, scanned only when the file changed since the last build.
+    pub fn requires(&self, path: &Path, code: &str) -> Vec<RequireMatch> {
+        self.requires_with(path, code, || find_literal_requires(code))
+    }
+
+    /// Like [`BuildCache::requires`], with `scan` computing the requires on a cache miss.
+    pub fn requires_with(
+        &self,
+        path: &Path,
+        code: &str,
+        scan: impl FnOnce() -> Vec<RequireMatch>,
+    ) -> Vec<RequireMatch> {
+        let hash = content_hash(code);
+        if let Some(found) = self.lookup(path, &hash, |e| e.requires.clone()) {
+            return found;
+        }
+        let found = scan();
+        self.store(path, hash, |e| e.requires = Some(found.clone()));
+        found
+    }
+
+    /// Prepared chunk for `code`, computed by `build` only on a cache miss. A file keeps
+    /// one transformed and one plain chunk; the transformed one is reused only with the
+    /// same `key.resolved`. `build` returns `None` for chunks that must not be cached
+    /// (e.g. invalid ones).
+    pub fn chunk(
+        &self,
+        path: &Path,
+        code: &str,
+        key: &ChunkKey,
+        build: impl FnOnce() -> Option<CachedChunk>,
+    ) -> Option<CachedChunk> {
+        let hash = content_hash(code);
+        let resolved = content_hash(&key.resolved);
+        let found = self.lookup(path, &hash, |e| {
+            if key.transform {
+                e.chunk.clone().filter(|_| e.chunk_resolved == resolved)
+            } else {
+                e.plain_chunk.clone()
+            }
+        });
+        if let Some(chunk) = found {
+            return Some(chunk);
+        }
+        let chunk = build()?;
+        self.store(path, hash, |e| {
+            if key.transform {
+                e.chunk = Some(chunk.clone());
+                e.chunk_resolved = resolved;
+            } else {
+                e.plain_chunk = Some(chunk.clone());
+            }
+        });
+        Some(chunk)
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Write the cache back to disk; a no-op for an in-memory cache.
+    pub fn save(&self) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create cache dir {}", dir.display()))?;
+        let file = CacheFile {
+            fingerprint: self.fingerprint.clone(),
+            entries: self.entries().clone(),
+        };
+        let path = dir.join(CACHE_FILE);
+        fs::write(&path, serde_json::to_string(&file)?)
+            .with_context(|| format!("failed to write cache {}", path.display()))
+    }
+
+    /// The entries, also after a thread panicked while holding them: every update leaves
+    /// them consistent, so a poisoned lock is safe to use.
+    fn entries(&self) -> MutexGuard<'_, HashMap<PathBuf, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lookup<T>(
+        &self,
+        path: &Path,
+        hash: &str,
+        get: impl FnOnce(&CacheEntry) -> Option<T>,
+    ) -> Option<T> {
+        let entries = self.entries();
+        let found = entries.get(path).filter(|e| e.hash == hash).and_then(get);
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    fn store(&self, path: &Path, hash: String, update: impl FnOnce(&mut CacheEntry)) {
+        let mut entries = self.entries();
+        let entry = entries.entry(path.to_path_buf()).or_default();
+        if entry.hash != hash {
+            *entry = CacheEntry {
+                hash,
+                ..Default::default()
+            };
+        }
+        update(entry);
+    }
+}
+
+fn content_hash(code: &str) -> String {
+    blake3::hash(code.as_bytes()).to_hex().to_string()
+}
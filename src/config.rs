@@ -7,15 +7,38 @@ use serde::Deserialize;
 #[derive(Debug, Default, Deserialize, Clone)]
 pub struct BundleConfig {
     pub lua: Option<String>,
+    pub strip_types: Option<bool>,
+    pub strip: Option<Vec<String>>,
+    pub strip_marked: Option<bool>,
+    pub downlevel: Option<bool>,
+    pub validate: Option<bool>,
     pub paths: Option<Vec<String>>,
     pub preludes: Option<Vec<String>>,
     pub replace: Option<Vec<String>>,
     pub vendors: Option<Vec<String>>,
+    pub vendor_conflict: Option<String>,
+    pub vendor_shadow: Option<String>,
+    pub externals: Option<Vec<String>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub stubs: Option<Vec<String>>,
+    pub globals: Option<Vec<String>>,
+    pub library: Option<Vec<String>>,
     pub output: Option<String>,
     pub entry: Option<String>,
     pub bind_require: Option<String>,
+    pub runtime_trace: Option<bool>,
+    pub introspect: Option<bool>,
+    pub hot_reload: Option<bool>,
+    pub sandbox: Option<String>,
+    pub sandbox_allow: Option<Vec<String>>,
+    pub instrument: Option<String>,
     pub diagnostics: Option<bool>,
     pub redact_base: Option<String>,
+    pub depfile: Option<String>,
+    pub verify: Option<bool>,
+    pub cache: Option<bool>,
+    pub cache_dir: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize, Clone)]
@@ -26,6 +49,8 @@ struct RootConfig {
 pub struct LoadedConfig {
     pub cfg: BundleConfig,
     pub dir: Option<PathBuf>,
+    /// The config file that was loaded, if any.
+    pub path: Option<PathBuf>,
 }
 
 pub fn load_config(explicit: Option<&Path>) -> Result<LoadedConfig> {
@@ -48,6 +73,7 @@ pub fn load_config(explicit: Option<&Path>) -> Result<LoadedConfig> {
     Ok(LoadedConfig {
         cfg: BundleConfig::default(),
         dir: None,
+        path: None,
     })
 }
 
@@ -61,7 +87,11 @@ fn load_from_path(path: &Path) -> Result<LoadedConfig> {
         .with_context(|| format!("failed to parse config at {}", path.display()))?;
     let cfg = root.bundle.unwrap_or_default();
     let dir = path.parent().map(|p| p.to_path_buf());
-    Ok(LoadedConfig { cfg, dir })
+    Ok(LoadedConfig {
+        cfg,
+        dir,
+        path: Some(path.to_path_buf()),
+    })
 }
 
 pub fn resolve_path_like(base: Option<&Path>, value: &str) -> String {
@@ -85,3 +115,23 @@ pub fn resolve_pathbuf(base: Option<&Path>, value: &str) -> PathBuf {
         p.to_path_buf()
     }
 }
+
+/// Name of the file listing first-party files never to bundle, next to the config file.
+pub const IGNORE_FILE: &str = ".luapackignore";
+
+/// Globs from the [`IGNORE_FILE`] in `dir`, one per line; blank lines and `#` comments are
+/// skipped. A missing file yields no globs.
+pub fn read_ignore_file(dir: &Path) -> Result<Vec<String>> {
+    let path = dir.join(IGNORE_FILE);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
@@ -0,0 +1,36 @@
+use std::path::{Path, PathBuf};
+
+/// Render a Make-syntax depfile (`output: dep dep ...`) as understood by make and ninja.
+///
+/// Dependencies are emitted in the given order with duplicates removed; long lists are
+/// wrapped with `\` continuations, one file per line.
+pub fn render_depfile(output: &Path, deps: &[PathBuf]) -> String {
+    let mut seen = std::collections::HashSet::new();
+    let mut out = format!("{}:", escape_make_path(output));
+    for dep in deps {
+        if !seen.insert(dep) {
+            continue;
+        }
+        out.push_str(" \\\n  ");
+        out.push_str(&escape_make_path(dep));
+    }
+    out.push('\n');
+    out
+}
+
+/// Escape a path for a Make rule: spaces and `#` get a backslash, `$` is doubled.
+/// Backslashes in Windows paths are written as `/`, which both make and ninja accept.
+pub fn escape_make_path(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    let mut out = String::with_capacity(raw.len());
+    for ch in raw.chars() {
+        match ch {
+            ' ' => out.push_str("\\ "),
+            '#' => out.push_str("\\#"),
+            '$' => out.push_str("$$"),
+            '\\' if cfg!(windows) => out.push('/'),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
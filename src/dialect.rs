@@ -0,0 +1,207 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Result;
+use full_moon::ast::Ast;
+use full_moon::LuaVersion;
+
+/// Lua dialect targeted by the bundle (`--lua`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LuaDialect {
+    #[default]
+    Lua51,
+    Lua52,
+    Lua53,
+    Lua54,
+    LuaJit,
+    Luau,
+}
+
+impl LuaDialect {
+    pub fn name(self) -> &'static str {
+        match self {
+            LuaDialect::Lua51 => "5.1",
+            LuaDialect::Lua52 => "5.2",
+            LuaDialect::Lua53 => "5.3",
+            LuaDialect::Lua54 => "5.4",
+            LuaDialect::LuaJit => "luajit",
+            LuaDialect::Luau => "luau",
+        }
+    }
+
+    /// Cargo feature needed to parse this dialect, `None` for the 5.1 baseline.
+    pub fn required_feature(self) -> Option<&'static str> {
+        match self {
+            LuaDialect::Lua51 => None,
+            LuaDialect::Lua52 => Some("lua52"),
+            LuaDialect::Lua53 => Some("lua53"),
+            LuaDialect::Lua54 => Some("lua54"),
+            LuaDialect::LuaJit => Some("luajit"),
+            LuaDialect::Luau => Some("luau"),
+        }
+    }
+
+    pub fn is_built(self) -> bool {
+        match self {
+            LuaDialect::Lua51 => true,
+            LuaDialect::Lua52 => cfg!(feature = "lua52"),
+            LuaDialect::Lua53 => cfg!(feature = "lua53"),
+            LuaDialect::Lua54 => cfg!(feature = "lua54"),
+            LuaDialect::LuaJit => cfg!(feature = "luajit"),
+            LuaDialect::Luau => cfg!(feature = "luau"),
+        }
+    }
+
+    /// full_moon version set that accepts exactly this dialect's syntax.
+    /// Falls back to every enabled version when the dialect was not built in.
+    pub fn lua_version(self) -> LuaVersion {
+        match self {
+            LuaDialect::Lua51 => LuaVersion::lua51(),
+            #[cfg(feature = "lua52")]
+            LuaDialect::Lua52 => LuaVersion::lua52(),
+            #[cfg(feature = "lua53")]
+            LuaDialect::Lua53 => LuaVersion::lua53(),
+            #[cfg(feature = "lua54")]
+            LuaDialect::Lua54 => LuaVersion::lua54(),
+            #[cfg(feature = "luajit")]
+            LuaDialect::LuaJit => LuaVersion::luajit(),
+            #[cfg(feature = "luau")]
+            LuaDialect::Luau => LuaVersion::luau(),
+            #[cfg(not(all(
+                feature = "lua52",
+                feature = "lua53",
+                feature = "lua54",
+                feature = "luajit",
+                feature = "luau"
+            )))]
+            _ => LuaVersion::new(),
+        }
+    }
+
+    pub fn has_integer_division(self) -> bool {
+        matches!(
+            self,
+            LuaDialect::Lua53 | LuaDialect::Lua54 | LuaDialect::Luau
+        )
+    }
+
+    pub fn has_bitwise_operators(self) -> bool {
+        matches!(self, LuaDialect::Lua53 | LuaDialect::Lua54)
+    }
+
+    pub fn has_attributes(self) -> bool {
+        matches!(self, LuaDialect::Lua54)
+    }
+
+    pub fn has_goto(self) -> bool {
+        !matches!(self, LuaDialect::Lua51 | LuaDialect::Luau)
+    }
+
+    /// Library used to emulate bitwise operators: `bit` (LuaJIT, LuaBitOp on 5.1) or `bit32` (5.2, Luau).
+    pub fn bit_library(self) -> Option<&'static str> {
+        match self {
+            LuaDialect::Lua51 | LuaDialect::LuaJit => Some("bit"),
+            LuaDialect::Lua52 | LuaDialect::Luau => Some("bit32"),
+            LuaDialect::Lua53 | LuaDialect::Lua54 => None,
+        }
+    }
+}
+
+impl FromStr for LuaDialect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let v = s.trim().to_ascii_lowercase();
+        if v.contains("luajit") {
+            Ok(LuaDialect::LuaJit)
+        } else if v == "luau" {
+            Ok(LuaDialect::Luau)
+        } else if v.starts_with("5.4") || v == "54" {
+            Ok(LuaDialect::Lua54)
+        } else if v.starts_with("5.3") || v == "53" {
+            Ok(LuaDialect::Lua53)
+        } else if v.starts_with("5.2") || v == "52" {
+            Ok(LuaDialect::Lua52)
+        } else if v.starts_with("5.1") || v == "51" {
+            Ok(LuaDialect::Lua51)
+        } else {
+            Err(anyhow::anyhow!("unknown Lua dialect: {}", s))
+        }
+    }
+}
+
+impl fmt::Display for LuaDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Every non-Luau version this build can parse.
+fn lua_superset() -> LuaVersion {
+    let v = LuaVersion::lua51();
+    #[cfg(feature = "lua52")]
+    let v = v | LuaVersion::lua52();
+    #[cfg(feature = "lua53")]
+    let v = v | LuaVersion::lua53();
+    #[cfg(feature = "lua54")]
+    let v = v | LuaVersion::lua54();
+    #[cfg(feature = "luajit")]
+    let v = v | LuaVersion::luajit();
+    v
+}
+
+/// Parse source of any supported dialect. Luau is tried only when the code is not
+/// valid PUC Lua/LuaJIT, so `::label::` and friends keep their Lua meaning.
+pub(crate) fn parse_lua(code: &str) -> Result<Ast, Vec<full_moon::Error>> {
+    // full_moon's non-Luau lexer panics on backtick strings once the luau feature is built.
+    #[cfg(feature = "luau")]
+    {
+        if has_interpolated_strings(code) {
+            return full_moon::parse_fallible(code, LuaVersion::luau()).into_result();
+        }
+    }
+    let res = full_moon::parse_fallible(code, lua_superset()).into_result();
+    #[cfg(feature = "luau")]
+    {
+        if res.is_err() {
+            if let Ok(ast) = full_moon::parse_fallible(code, LuaVersion::luau()).into_result() {
+                return Ok(ast);
+            }
+        }
+    }
+    res
+}
+
+#[cfg(feature = "luau")]
+fn has_interpolated_strings(code: &str) -> bool {
+    use full_moon::tokenizer::{Lexer, LexerResult, TokenType};
+
+    if !code.contains('`') {
+        return false;
+    }
+    let tokens = match Lexer::new(code, LuaVersion::luau()).collect() {
+        LexerResult::Ok(tokens) | LexerResult::Recovered(tokens, _) => tokens,
+        LexerResult::Fatal(_) => return true,
+    };
+    tokens
+        .iter()
+        .any(|t| matches!(t.token_type(), TokenType::InterpolatedString { .. }))
+}
+
+/// A construct in a source file that the target dialect cannot express.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialectIssue {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+/// Render issues as `file:line:col: message` lines.
+pub fn format_issues(file: &Path, issues: &[DialectIssue]) -> String {
+    issues
+        .iter()
+        .map(|i| format!("{}:{}:{}: {}", file.display(), i.line, i.col, i.message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,242 @@
+#![cfg_attr(
+    not(feature = "lua53"),
+    expect(
+        dead_code,
+        reason = "without the lua53 feature there is no newer syntax to lower"
+    )
+)]
+
+use full_moon::ast::punctuated::{Pair, Punctuated};
+use full_moon::ast::span::ContainedSpan;
+use full_moon::ast::{
+    self, Ast, BinOp, Call, Expression, FunctionArgs, FunctionCall, Index, Prefix, Suffix, UnOp,
+};
+use full_moon::node::Node;
+use full_moon::tokenizer::{Symbol, Token, TokenReference, TokenType};
+use full_moon::visitors::VisitorMut;
+
+use crate::dialect::{parse_lua, DialectIssue, LuaDialect};
+use crate::trivia::whitespace;
+#[cfg(feature = "lua53")]
+use crate::trivia::{significant_trivia, split_trivia};
+
+/// Rewrite syntax newer than `target` into an equivalent the target understands.
+///
+/// - `local x <const>` drops the attribute; `<close>` is rejected.
+/// - `a // b` becomes `math.floor(a / b)`.
+/// - Bitwise operators become `bit.*` calls (LuaJIT, 5.1 with LuaBitOp) or `bit32.*` calls (5.2).
+/// - `goto` and labels are rejected on 5.1.
+///
+/// Returns the rewritten code and the number of rewrites, or every construct that
+/// could not be lowered. Code that fails to parse is returned unchanged.
+pub fn downlevel(code: &str, target: LuaDialect) -> Result<(String, usize), Vec<DialectIssue>> {
+    match parse_lua(code) {
+        Ok(ast) => downlevel_ast(ast, target).map(|(ast, n)| (ast.to_string(), n)),
+        Err(_) => Ok((code.to_string(), 0)),
+    }
+}
+
+/// [`downlevel`] for an already parsed chunk.
+pub(crate) fn downlevel_ast(
+    ast: Ast,
+    target: LuaDialect,
+) -> Result<(Ast, usize), Vec<DialectIssue>> {
+    let mut v = Downleveler {
+        target,
+        rewrites: 0,
+        issues: Vec::new(),
+    };
+    let new_ast = v.visit_ast(ast);
+    if !v.issues.is_empty() {
+        return Err(v.issues);
+    }
+    Ok((new_ast, v.rewrites))
+}
+
+struct Downleveler {
+    target: LuaDialect,
+    rewrites: usize,
+    issues: Vec<DialectIssue>,
+}
+
+impl Downleveler {
+    fn issue(&mut self, node: &impl Node, message: String) {
+        let (line, col) = node
+            .start_position()
+            .map(|p| (p.line(), p.character()))
+            .unwrap_or((0, 0));
+        self.issues.push(DialectIssue { line, col, message });
+    }
+
+    fn lower_binary(&mut self, lhs: Expression, binop: BinOp, rhs: Expression) -> Expression {
+        #[cfg(feature = "lua53")]
+        {
+            if let BinOp::DoubleSlash(tok) = &binop {
+                if !self.target.has_integer_division() {
+                    let slash = tok.with_token(Token::new(TokenType::Symbol {
+                        symbol: Symbol::Slash,
+                    }));
+                    let (leading, lhs, _) = split_trivia(lhs, true, false);
+                    let (_, rhs, trailing) = split_trivia(rhs, false, true);
+                    let quotient = Expression::BinaryOperator {
+                        lhs: Box::new(lhs),
+                        binop: BinOp::Slash(slash),
+                        rhs: Box::new(rhs),
+                    };
+                    self.rewrites += 1;
+                    return lib_call("math", "floor", vec![quotient], leading, vec![], trailing);
+                }
+            }
+            let func = match &binop {
+                BinOp::Ampersand(_) => Some("band"),
+                BinOp::Pipe(_) => Some("bor"),
+                BinOp::Tilde(_) => Some("bxor"),
+                BinOp::DoubleLessThan(_) => Some("lshift"),
+                BinOp::DoubleGreaterThan(_) => Some("rshift"),
+                _ => None,
+            };
+            if let (Some(func), Some(lib)) = (func, self.target.bit_library()) {
+                let (leading, lhs, gap) = split_trivia(lhs, true, true);
+                let (_, rhs, trailing) = split_trivia(rhs, false, true);
+                self.rewrites += 1;
+                let gap = significant_trivia(gap);
+                return lib_call(lib, func, vec![lhs, rhs], leading, gap, trailing);
+            }
+        }
+        Expression::BinaryOperator {
+            lhs: Box::new(lhs),
+            binop,
+            rhs: Box::new(rhs),
+        }
+    }
+
+    fn lower_unary(&mut self, unop: UnOp, expression: Expression) -> Expression {
+        #[cfg(feature = "lua53")]
+        {
+            if let (UnOp::Tilde(tok), Some(lib)) = (&unop, self.target.bit_library()) {
+                let leading = tok.leading_trivia().cloned().collect();
+                let (_, expression, trailing) = split_trivia(expression, false, true);
+                self.rewrites += 1;
+                return lib_call(lib, "bnot", vec![expression], leading, vec![], trailing);
+            }
+        }
+        Expression::UnaryOperator {
+            unop,
+            expression: Box::new(expression),
+        }
+    }
+}
+
+impl VisitorMut for Downleveler {
+    fn visit_local_assignment(&mut self, node: ast::LocalAssignment) -> ast::LocalAssignment {
+        #[cfg(feature = "lua54")]
+        {
+            if !self.target.has_attributes() && node.attributes().any(|a| a.is_some()) {
+                let mut rejected = false;
+                let attrs: Vec<ast::lua54::Attribute> =
+                    node.attributes().flatten().cloned().collect();
+                for attr in &attrs {
+                    if attr.name().token().to_string() == "close" {
+                        rejected = true;
+                        self.issue(
+                            attr,
+                            format!(
+                                "`<close>` attribute is not supported by Lua {}; close the resource explicitly",
+                                self.target
+                            ),
+                        );
+                    }
+                }
+                if !rejected {
+                    self.rewrites += attrs.len();
+                    return node.with_attributes(Vec::new());
+                }
+            }
+        }
+        node
+    }
+
+    fn visit_expression_end(&mut self, node: Expression) -> Expression {
+        match node {
+            Expression::BinaryOperator { lhs, binop, rhs } => self.lower_binary(*lhs, binop, *rhs),
+            Expression::UnaryOperator { unop, expression } => self.lower_unary(unop, *expression),
+            other => other,
+        }
+    }
+
+    #[cfg(any(feature = "lua52", feature = "luajit"))]
+    fn visit_goto(&mut self, node: ast::lua52::Goto) -> ast::lua52::Goto {
+        if !self.target.has_goto() {
+            self.issue(
+                &node,
+                format!("`goto` is not supported by Lua {}", self.target),
+            );
+        }
+        node
+    }
+
+    #[cfg(any(feature = "lua52", feature = "luajit"))]
+    fn visit_label(&mut self, node: ast::lua52::Label) -> ast::lua52::Label {
+        if !self.target.has_goto() {
+            self.issue(
+                &node,
+                format!("labels are not supported by Lua {}", self.target),
+            );
+        }
+        node
+    }
+}
+
+fn ident(name: &str, leading: Vec<Token>) -> TokenReference {
+    let t = Token::new(TokenType::Identifier {
+        identifier: name.into(),
+    });
+    TokenReference::new(leading, t, vec![])
+}
+
+fn symbol(symbol: Symbol, trailing: Vec<Token>) -> TokenReference {
+    TokenReference::new(vec![], Token::new(TokenType::Symbol { symbol }), trailing)
+}
+
+/// Build `lib.func(args...)` carrying the given outer trivia; `gap` precedes the first comma.
+fn lib_call(
+    lib: &str,
+    func: &str,
+    args: Vec<Expression>,
+    leading: Vec<Token>,
+    gap: Vec<Token>,
+    trailing: Vec<Token>,
+) -> Expression {
+    let mut gap = Some(gap);
+    let mut arguments = Punctuated::new();
+    let count = args.len();
+    for (i, arg) in args.into_iter().enumerate() {
+        if i + 1 < count {
+            let space = whitespace(" ");
+            let comma = TokenReference::new(
+                gap.take().unwrap_or_default(),
+                Token::new(TokenType::Symbol {
+                    symbol: Symbol::Comma,
+                }),
+                vec![space],
+            );
+            arguments.push(Pair::Punctuated(arg, comma));
+        } else {
+            arguments.push(Pair::End(arg));
+        }
+    }
+    let call = FunctionCall::new(Prefix::Name(ident(lib, leading))).with_suffixes(vec![
+        Suffix::Index(Index::Dot {
+            dot: symbol(Symbol::Dot, vec![]),
+            name: ident(func, vec![]),
+        }),
+        Suffix::Call(Call::AnonymousCall(FunctionArgs::Parentheses {
+            parentheses: ContainedSpan::new(
+                symbol(Symbol::LeftParen, vec![]),
+                symbol(Symbol::RightParen, trailing),
+            ),
+            arguments,
+        })),
+    ]);
+    Expression::FunctionCall(call)
+}
@@ -0,0 +1,362 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::graph::{ModuleGraph, ROOT_MODULE};
+use crate::graph_export::{classify_graph, GraphExportCtx, ModuleKind};
+use crate::normalize::normalize_module_name;
+use crate::replace::{MatchKind, ReplaceRule};
+use crate::transform::{apply_replace, rule_applies_to_file};
+use crate::vendor::{path_rule_matches, to_glob_and_root, vendor_raw_name, VendorSpec};
+
+/// Why a module is (or is not) part of the bundle, as printed by `luapack why`.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// Normalized module name.
+    pub module: String,
+    pub kind: Option<ModuleKind>,
+    /// Shortest require chain from the entry, starting with [`ROOT_MODULE`].
+    pub chain: Option<Vec<String>>,
+    /// Shortest chain from an `--include` root, when the entry does not require the module.
+    pub include_chain: Option<Vec<String>>,
+    pub source: ModuleSource,
+    /// Spellings of the module that lost a normalizer suffix, with the suffixes removed.
+    pub stripped: Vec<(String, Vec<String>)>,
+    /// Every literal require of the module and what the replace rules made of it.
+    pub requires: Vec<RequireVerdicts>,
+}
+
+/// Where the file behind a module name comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleSource {
+    /// Resolved by a `--path` template.
+    Path {
+        template: String,
+        path: PathBuf,
+    },
+    /// Found by a `--path` template but excluded from the bundle (`--exclude`, `.luapackignore`).
+    Excluded {
+        template: String,
+        path: PathBuf,
+    },
+    /// Collected from a `--vendor` template.
+    Vendor {
+        template: String,
+        path: PathBuf,
+    },
+    /// A vendor template finds the file but the module is left out, with the reason.
+    VendorSkipped {
+        template: String,
+        path: PathBuf,
+        reason: String,
+    },
+    NotFound,
+}
+
+#[derive(Debug, Clone)]
+pub struct RequireVerdicts {
+    /// Requiring module, [`ROOT_MODULE`] for the entry.
+    pub from: String,
+    pub file: PathBuf,
+    pub line: usize,
+    /// Module string as written.
+    pub request: String,
+    pub rules: Vec<RuleVerdict>,
+}
+
+/// Outcome of one replace rule for one require.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleVerdict {
+    /// Position of the rule in the `--replace` list, starting at 0.
+    pub index: usize,
+    /// The rule in `--replace` syntax.
+    pub rule: String,
+    pub outcome: RuleOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleOutcome {
+    /// The rule rewrites the require to `callee("arg")`.
+    Rewrites { callee: String, arg: String },
+    /// An earlier rule already matched.
+    Shadowed,
+    /// The rule does not match, with the reason.
+    NoMatch(String),
+}
+
+/// Explain how `module` ends up in (or out of) the bundle built from `graph`.
+pub fn explain_module(
+    graph: &ModuleGraph,
+    ctx: &GraphExportCtx,
+    vendor_specs: &[VendorSpec],
+    module: &str,
+) -> Explanation {
+    let name = normalize_module_name(module, ctx.normalizer);
+    let kind = classify_graph(graph, ctx)
+        .nodes
+        .into_iter()
+        .find(|n| n.name == name)
+        .map(|n| n.kind);
+    let chain = graph.require_chain(&name).map(|edges| {
+        std::iter::once(ROOT_MODULE.to_string())
+            .chain(edges.into_iter().map(|e| e.to.clone()))
+            .collect()
+    });
+    let include_chain = match chain {
+        Some(_) => None,
+        None => graph.include_chain(&name),
+    };
+
+    let mut spellings = vec![module.to_string()];
+    let mut requires: Vec<RequireVerdicts> = Vec::new();
+    for edge in graph.edges.iter().filter(|e| e.to == name) {
+        let file = if edge.from == ROOT_MODULE {
+            ctx.entry_path.to_path_buf()
+        } else {
+            edge.file.clone()
+        };
+        if requires
+            .iter()
+            .any(|r| r.file == file && r.request == edge.request && r.line == edge.line)
+        {
+            continue;
+        }
+        spellings.push(edge.request.clone());
+        requires.push(RequireVerdicts {
+            from: edge.from.clone(),
+            rules: judge_rules(&edge.request, &file, ctx),
+            file,
+            line: edge.line,
+            request: edge.request.clone(),
+        });
+    }
+
+    // A vendor module takes any name the first-party graph leaves free.
+    let vendor_first = ctx.vendor_mods.contains_key(&name)
+        && !graph.first_party.contains_key(&name)
+        && !graph.aliases.contains_key(&name);
+    let source = find_source(&name, module, ctx, vendor_specs, vendor_first);
+    if let ModuleSource::Vendor { template, path }
+    | ModuleSource::VendorSkipped { template, path, .. } = &source
+    {
+        let (_, root, init_mode) = to_glob_and_root(template);
+        spellings.extend(vendor_raw_name(path, &root, init_mode));
+    }
+    let mut stripped: Vec<(String, Vec<String>)> = Vec::new();
+    for spelling in spellings {
+        let suffixes = ctx.normalizer.stripped_suffixes(&spelling);
+        if !suffixes.is_empty() && !stripped.iter().any(|(s, _)| *s == spelling) {
+            stripped.push((spelling, suffixes));
+        }
+    }
+
+    Explanation {
+        module: name,
+        kind,
+        chain,
+        include_chain,
+        source,
+        stripped,
+        requires,
+    }
+}
+
+fn find_source(
+    name: &str,
+    module: &str,
+    ctx: &GraphExportCtx,
+    vendor_specs: &[VendorSpec],
+    vendor_first: bool,
+) -> ModuleSource {
+    let spellings = if vendor_first {
+        &[][..]
+    } else {
+        &[module, name][..]
+    };
+    for &spelling in spellings {
+        if let Some((template, path)) = ctx.resolver.and_then(|r| r.resolve_with_template(spelling))
+        {
+            return ModuleSource::Path {
+                template: template.to_string(),
+                path,
+            };
+        }
+    }
+    for spec in vendor_specs {
+        for template in &spec.paths {
+            let (glob_pat, root, init_mode) = to_glob_and_root(template);
+            let Ok(paths) = glob::glob(&glob_pat) else {
+                continue;
+            };
+            for path in paths.flatten() {
+                let found = vendor_raw_name(&path, &root, init_mode)
+                    .is_some_and(|raw| normalize_module_name(&raw, ctx.normalizer) == name);
+                if !found || !path.is_file() {
+                    continue;
+                }
+                let template = template.clone();
+                if ctx.vendor_mods.get(name) == Some(&path) {
+                    return ModuleSource::Vendor { template, path };
+                }
+                let reason = if let Some(reason) = spec.rejects(name, &path) {
+                    reason
+                } else if path_rule_matches(ctx.replaces, &path) {
+                    "its file matches a match=path replace rule".to_string()
+                } else if ctx.vendor_mods.contains_key(name) {
+                    "an earlier vendor template provides the same name".to_string()
+                } else {
+                    "matched by a replace rule".to_string()
+                };
+                return ModuleSource::VendorSkipped {
+                    template,
+                    path,
+                    reason,
+                };
+            }
+        }
+    }
+    for spelling in [module, name] {
+        if let Some((template, path)) = ctx.resolver.and_then(|r| r.excluded(spelling)) {
+            return ModuleSource::Excluded {
+                template: template.to_string(),
+                path,
+            };
+        }
+    }
+    ModuleSource::NotFound
+}
+
+fn judge_rules(request: &str, file: &Path, ctx: &GraphExportCtx) -> Vec<RuleVerdict> {
+    let mut matched = false;
+    let mut verdicts = Vec::new();
+    for (index, rule) in ctx.replaces.iter().enumerate() {
+        let outcome = if matched {
+            RuleOutcome::Shadowed
+        } else if rule.match_kind != MatchKind::Path && !rule_applies_to_file(rule, file) {
+            RuleOutcome::NoMatch(format!(
+                "only applies in files matching {}",
+                rule.paths.join(", ")
+            ))
+        } else if let Some((callee, arg)) =
+            apply_replace(request, rule, ctx.resolver, ctx.normalizer)
+        {
+            matched = true;
+            RuleOutcome::Rewrites { callee, arg }
+        } else {
+            RuleOutcome::NoMatch(mismatch_reason(request, rule, ctx))
+        };
+        verdicts.push(RuleVerdict {
+            index,
+            rule: rule.to_string(),
+            outcome,
+        });
+    }
+    verdicts
+}
+
+fn mismatch_reason(request: &str, rule: &ReplaceRule, ctx: &GraphExportCtx) -> String {
+    let name = normalize_module_name(request, ctx.normalizer);
+    match rule.match_kind {
+        MatchKind::Exact => match &rule.name {
+            Some(n) => format!("`{}` is not `{}`", name, n),
+            None => "exact rule without name=".to_string(),
+        },
+        MatchKind::Prefix => match &rule.prefix {
+            Some(p) => format!("`{}` does not start with `{}`", name, p),
+            None => "prefix rule without prefix=".to_string(),
+        },
+        MatchKind::Path => match ctx.resolver.and_then(|r| r.find(&name)).map(|(_, p)| p) {
+            None => format!("`{}` does not resolve under --path", name),
+            Some(path) => format!(
+                "{} matches none of {}",
+                path.to_string_lossy().replace('\\', "/"),
+                rule.paths.join(", ")
+            ),
+        },
+    }
+}
+
+/// Render an [`Explanation`] as the plain-text report of `luapack why`.
+pub fn format_explanation(ex: &Explanation) -> String {
+    let mut out = String::new();
+    let kind = ex.kind.map(ModuleKind::as_str).unwrap_or("not required");
+    let _ = writeln!(out, "module: {} ({})", ex.module, kind);
+    match (&ex.chain, &ex.include_chain) {
+        (Some(chain), _) => {
+            let _ = writeln!(out, "chain: {}", chain.join(" -> "));
+        }
+        (None, Some(chain)) => {
+            let _ = writeln!(out, "chain: --include {}", chain.join(" -> "));
+        }
+        (None, None) => out.push_str("chain: not required from the entry\n"),
+    }
+    let _ = match &ex.source {
+        ModuleSource::Path { template, path } => writeln!(
+            out,
+            "resolved: {} via --path template {}",
+            path.display(),
+            template
+        ),
+        ModuleSource::Excluded { template, path } => writeln!(
+            out,
+            "resolved: {} via --path template {}, but not bundled: excluded",
+            path.display(),
+            template
+        ),
+        ModuleSource::Vendor { template, path } => writeln!(
+            out,
+            "resolved: {} via --vendor template {}",
+            path.display(),
+            template
+        ),
+        ModuleSource::VendorSkipped {
+            template,
+            path,
+            reason,
+        } => writeln!(
+            out,
+            "resolved: {} via --vendor template {}, but not bundled: {}",
+            path.display(),
+            template,
+            reason
+        ),
+        ModuleSource::NotFound => writeln!(out, "resolved: no --path or --vendor template"),
+    };
+    if ex.stripped.is_empty() {
+        out.push_str("suffix: none stripped\n");
+    }
+    for (spelling, suffixes) in &ex.stripped {
+        let suffixes: Vec<String> = suffixes.iter().map(|s| format!(".{}", s)).collect();
+        let _ = writeln!(
+            out,
+            "suffix: stripped {} from {}",
+            suffixes.join(", "),
+            spelling
+        );
+    }
+    for req in &ex.requires {
+        let location = if req.line > 0 {
+            format!("{}:{}", req.file.display(), req.line)
+        } else {
+            req.file.display().to_string()
+        };
+        let _ = writeln!(
+            out,
+            "require(\"{}\") in {} ({}):",
+            req.request, req.from, location
+        );
+        if req.rules.is_empty() {
+            out.push_str("  no replace rules\n");
+        }
+        for v in &req.rules {
+            let outcome = match &v.outcome {
+                RuleOutcome::Rewrites { callee, arg } => {
+                    format!("matches, rewritten to {}(\"{}\")", callee, arg)
+                }
+                RuleOutcome::Shadowed => "not reached, an earlier rule matched".to_string(),
+                RuleOutcome::NoMatch(reason) => format!("no match: {}", reason),
+            };
+            let _ = writeln!(out, "  rule #{} {}: {}", v.index, v.rule, outcome);
+        }
+    }
+    out
+}
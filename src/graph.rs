@@ -2,14 +2,81 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::PathBuf;
 
+use full_moon::ast::Ast;
+
+use crate::cache::BuildCache;
+use crate::dialect::parse_lua;
 use crate::normalize::normalize_module_name;
 use crate::options::NameNormalizer;
+use crate::parallel::parallel_map;
 use crate::resolve::ModuleResolver;
-use crate::scan::find_literal_requires;
+use crate::scan::{requires_in_ast, RequireMatch};
+
+/// Module name of the entry chunk, as registered in the bundle.
+pub const ROOT_MODULE: &str = "__root";
 
 pub struct ModuleGraph {
     pub first_party: HashMap<String, PathBuf>,
     pub unresolved: HashSet<String>,
+    /// Required modules whose file is excluded from the bundle, treated as external.
+    pub excluded: HashMap<String, PathBuf>,
+    /// Modules walked as extra roots (`--include`), whether or not a require reaches them.
+    pub roots: Vec<String>,
+    /// Every literal require found while walking the graph, in discovery order.
+    pub edges: Vec<ModuleEdge>,
+    /// Further names that resolve to a first-party file, mapped to the name it is registered under.
+    pub aliases: HashMap<String, String>,
+    /// Parsed first-party files by path, reused when generating the bundle.
+    pub units: HashMap<PathBuf, ModuleUnit>,
+    /// The entry chunk the graph was built from.
+    pub entry: Option<ModuleUnit>,
+}
+
+/// A source file read and parsed once: its text, literal requires and syntax tree.
+pub struct ModuleUnit {
+    pub path: PathBuf,
+    pub source: String,
+    pub requires: Vec<RequireMatch>,
+    /// `None` when the requires came from the build cache or the file does not parse.
+    pub(crate) ast: Option<Ast>,
+}
+
+/// A literal `require` from one module of the graph to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleEdge {
+    /// Requiring module; [`ROOT_MODULE`] for the entry chunk.
+    pub from: String,
+    /// Normalized name of the required module.
+    pub to: String,
+    /// The module string as written in the source.
+    pub request: String,
+    /// File containing the require; empty for the entry chunk.
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+impl ModuleUnit {
+    pub(crate) fn parse(path: PathBuf, source: String, cache: Option<&BuildCache>) -> Self {
+        let mut ast = None;
+        let mut scan = |code: &str| match parse_lua(code) {
+            Ok(parsed) => {
+                let found = requires_in_ast(&parsed);
+                ast = Some(parsed);
+                found
+            }
+            Err(_) => Vec::new(),
+        };
+        let requires = match cache {
+            Some(cache) => cache.requires_with(&path, &source, || scan(&source)),
+            None => scan(&source),
+        };
+        Self {
+            path,
+            source,
+            requires,
+            ast,
+        }
+    }
 }
 
 impl ModuleGraph {
@@ -17,45 +84,240 @@ impl ModuleGraph {
         entry_code: &str,
         resolver: &ModuleResolver,
         normalizer: &NameNormalizer,
+    ) -> Self {
+        Self::build_with_cache(entry_code, resolver, normalizer, None)
+    }
+
+    /// Like [`ModuleGraph::build_from_entry_code`], reusing cached require scans of unchanged files.
+    ///
+    /// Modules are discovered breadth-first; each level of newly found files is read and
+    /// parsed in parallel.
+    pub fn build_with_cache(
+        entry_code: &str,
+        resolver: &ModuleResolver,
+        normalizer: &NameNormalizer,
+        cache: Option<&BuildCache>,
+    ) -> Self {
+        Self::build_with_roots(entry_code, &[], resolver, normalizer, cache)
+    }
+
+    /// Like [`ModuleGraph::build_with_cache`], also walking from `roots` (module name and
+    /// file), e.g. the modules `--include` names for dynamic requires.
+    ///
+    /// Roots are bundled with everything they require, but no edge leads to them.
+    pub fn build_with_roots(
+        entry_code: &str,
+        roots: &[(String, PathBuf)],
+        resolver: &ModuleResolver,
+        normalizer: &NameNormalizer,
+        cache: Option<&BuildCache>,
     ) -> Self {
         let mut first_party: HashMap<String, PathBuf> = HashMap::new();
         let mut unresolved: HashSet<String> = HashSet::new();
-        let mut visited_paths: HashSet<PathBuf> = HashSet::new();
-        let mut q: VecDeque<(String, PathBuf)> = VecDeque::new();
+        let mut excluded: HashMap<String, PathBuf> = HashMap::new();
+        let mut units: HashMap<PathBuf, ModuleUnit> = HashMap::new();
+        // Canonical path of every visited file -> the first name that reached it.
+        let mut visited_paths: HashMap<PathBuf, String> = HashMap::new();
+        let mut aliases: HashMap<String, String> = HashMap::new();
+        let mut edges: Vec<ModuleEdge> = Vec::new();
 
-        for r in find_literal_requires(entry_code) {
-            let n = normalize_module_name(&r.module, normalizer);
-            if let Some(path) = resolver.resolve(&r.module) {
-                q.push_back((n, path));
-            } else {
-                unresolved.insert(n);
+        let entry = ModuleUnit::parse(PathBuf::new(), entry_code.to_string(), None);
+        record_edges(&mut edges, ROOT_MODULE, &entry, normalizer);
+        let mut frontier = resolve_requires(
+            &entry.requires,
+            resolver,
+            normalizer,
+            &mut unresolved,
+            &mut excluded,
+        );
+        frontier.extend(roots.iter().cloned());
+
+        while !frontier.is_empty() {
+            let mut level: Vec<(String, PathBuf)> = Vec::new();
+            for (mod_name, path) in frontier {
+                let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                if let Some(first) = visited_paths.get(&key) {
+                    if *first != mod_name {
+                        aliases.insert(mod_name, first.clone());
+                    }
+                    continue;
+                }
+                visited_paths.insert(key, mod_name.clone());
+                first_party.insert(mod_name.clone(), path.clone());
+                level.push((mod_name, path));
+            }
+
+            let loaded = parallel_map(level, |(mod_name, path)| {
+                let source = fs::read_to_string(&path).ok()?;
+                Some((mod_name, ModuleUnit::parse(path, source, cache)))
+            });
+
+            frontier = Vec::new();
+            for (mod_name, unit) in loaded.into_iter().flatten() {
+                record_edges(&mut edges, &mod_name, &unit, normalizer);
+                for (n, p) in resolve_requires(
+                    &unit.requires,
+                    resolver,
+                    normalizer,
+                    &mut unresolved,
+                    &mut excluded,
+                ) {
+                    if !first_party.contains_key(&n) && !aliases.contains_key(&n) {
+                        frontier.push((n, p));
+                    }
+                }
+                units.insert(unit.path.clone(), unit);
             }
         }
 
-        while let Some((mod_name, path)) = q.pop_front() {
-            if visited_paths.contains(&path) {
+        // The bundle looks modules up by the string passed to `require`, so spellings that
+        // only normalize to a registered name (e.g. `core.greet.init`) need an alias too.
+        for edge in &edges {
+            if edge.request == edge.to || first_party.contains_key(&edge.request) {
                 continue;
             }
-            visited_paths.insert(path.clone());
-            first_party.entry(mod_name.clone()).or_insert(path.clone());
-
-            if let Ok(code) = fs::read_to_string(&path) {
-                for r in find_literal_requires(&code) {
-                    let n = normalize_module_name(&r.module, normalizer);
-                    if let Some(p) = resolver.resolve(&r.module) {
-                        if !visited_paths.contains(&p) {
-                            q.push_back((n, p));
-                        }
-                    } else {
-                        unresolved.insert(n);
-                    }
-                }
+            let target = aliases.get(&edge.to).unwrap_or(&edge.to);
+            if first_party.contains_key(target) {
+                let target = target.clone();
+                aliases.entry(edge.request.clone()).or_insert(target);
             }
         }
 
         Self {
             first_party,
             unresolved,
+            excluded,
+            roots: roots.iter().map(|(name, _)| name.clone()).collect(),
+            edges,
+            aliases,
+            units,
+            entry: Some(entry),
+        }
+    }
+
+    /// Remove the first-party modules `names`, then every module, edge and alias that only
+    /// they reached from the entry or an `--include` root. Aliases of a removed module are
+    /// kept, pointing at its name.
+    pub fn drop_modules(&mut self, names: &HashSet<String>) {
+        fn registered<'a>(aliases: &'a HashMap<String, String>, name: &'a str) -> &'a str {
+            aliases.get(name).map_or(name, String::as_str)
+        }
+        let mut reachable: HashSet<String> = HashSet::from([ROOT_MODULE.to_string()]);
+        let mut queue: VecDeque<String> = VecDeque::from([ROOT_MODULE.to_string()]);
+        for root in &self.roots {
+            let root = registered(&self.aliases, root);
+            if !names.contains(root) && reachable.insert(root.to_string()) {
+                queue.push_back(root.to_string());
+            }
+        }
+        while let Some(from) = queue.pop_front() {
+            for edge in self.edges.iter().filter(|e| e.from == from) {
+                let to = registered(&self.aliases, &edge.to);
+                if names.contains(&edge.to) || names.contains(to) {
+                    continue;
+                }
+                if reachable.insert(to.to_string()) {
+                    queue.push_back(to.to_string());
+                }
+            }
+        }
+
+        self.edges.retain(|e| reachable.contains(&e.from));
+        let required: HashSet<&String> = self.edges.iter().map(|e| &e.to).collect();
+        self.unresolved.retain(|n| required.contains(n));
+        self.excluded.retain(|n, _| required.contains(n));
+        self.first_party.retain(|n, _| reachable.contains(n));
+        // Other names of a dropped module stay, and now lead to whatever replaces it.
+        self.aliases.retain(|alias, target| {
+            !names.contains(alias) && (reachable.contains(target) || names.contains(target))
+        });
+        self.roots.retain(|r| !names.contains(r));
+        let kept: HashSet<&PathBuf> = self.first_party.values().collect();
+        self.units.retain(|path, _| kept.contains(path));
+    }
+
+    /// Shortest chain of requires from the entry chunk to `module`, if it is required at all.
+    pub fn require_chain(&self, module: &str) -> Option<Vec<&ModuleEdge>> {
+        self.chain_from(ROOT_MODULE, module)
+    }
+
+    /// Shortest chain of module names from an `--include` root to `module`, for modules the
+    /// entry does not require. Starts with the root; just the root when `module` is one.
+    pub fn include_chain(&self, module: &str) -> Option<Vec<String>> {
+        if self.roots.iter().any(|r| r == module) {
+            return Some(vec![module.to_string()]);
+        }
+        self.roots
+            .iter()
+            .filter_map(|root| {
+                let edges = self.chain_from(root, module)?;
+                Some(
+                    std::iter::once(root.clone())
+                        .chain(edges.into_iter().map(|e| e.to.clone()))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .min_by_key(Vec::len)
+    }
+
+    fn chain_from(&self, start: &str, module: &str) -> Option<Vec<&ModuleEdge>> {
+        let mut via: HashMap<&str, &ModuleEdge> = HashMap::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([start]);
+        while let Some(from) = queue.pop_front() {
+            for edge in self.edges.iter().filter(|e| e.from == from) {
+                if edge.to == start || via.contains_key(edge.to.as_str()) {
+                    continue;
+                }
+                via.insert(&edge.to, edge);
+                if edge.to == module {
+                    let mut chain = vec![edge];
+                    while let Some(prev) = via.get(chain[chain.len() - 1].from.as_str()) {
+                        chain.push(prev);
+                    }
+                    chain.reverse();
+                    return Some(chain);
+                }
+                queue.push_back(&edge.to);
+            }
+        }
+        None
+    }
+}
+
+fn record_edges(
+    edges: &mut Vec<ModuleEdge>,
+    from: &str,
+    unit: &ModuleUnit,
+    normalizer: &NameNormalizer,
+) {
+    for r in &unit.requires {
+        edges.push(ModuleEdge {
+            from: from.to_string(),
+            to: normalize_module_name(&r.module, normalizer),
+            request: r.module.clone(),
+            file: unit.path.clone(),
+            line: r.line,
+        });
+    }
+}
+
+fn resolve_requires(
+    requires: &[RequireMatch],
+    resolver: &ModuleResolver,
+    normalizer: &NameNormalizer,
+    unresolved: &mut HashSet<String>,
+    excluded: &mut HashMap<String, PathBuf>,
+) -> Vec<(String, PathBuf)> {
+    let mut found = Vec::new();
+    for r in requires {
+        let n = normalize_module_name(&r.module, normalizer);
+        if let Some(path) = resolver.resolve(&r.module) {
+            found.push((n, path));
+        } else if let Some((_, path)) = resolver.excluded(&r.module) {
+            excluded.insert(n, path);
+        } else {
+            unresolved.insert(n);
         }
     }
+    found
 }
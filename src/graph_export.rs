@@ -0,0 +1,307 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use glob::Pattern;
+use serde::Serialize;
+
+use crate::graph::{ModuleGraph, ROOT_MODULE};
+use crate::options::NameNormalizer;
+use crate::replace::ReplaceRule;
+use crate::resolve::ModuleResolver;
+use crate::transform::find_replacement;
+
+/// Output format of `luapack graph`.
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+    Mermaid,
+}
+
+/// How a required module ends up being provided at runtime.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleKind {
+    /// Resolved under a `--path` template and bundled.
+    FirstParty,
+    /// Bundled from a `--vendor` root.
+    Vendor,
+    /// The require is rewritten by a `--replace` rule.
+    Replaced,
+    /// Matches an `--external` glob: left to the host's `require`.
+    External,
+    /// Not found anywhere.
+    Unresolved,
+}
+
+impl ModuleKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModuleKind::FirstParty => "first-party",
+            ModuleKind::Vendor => "vendor",
+            ModuleKind::Replaced => "replaced",
+            ModuleKind::External => "external",
+            ModuleKind::Unresolved => "unresolved",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub kind: ModuleKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: ModuleKind,
+}
+
+/// The module graph with every node and edge classified, ready to render.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphExport {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Bundle settings needed to classify the modules of a graph.
+pub struct GraphExportCtx<'a> {
+    pub entry_path: &'a Path,
+    pub replaces: &'a [ReplaceRule],
+    pub vendor_mods: &'a HashMap<String, PathBuf>,
+    /// Module-name globs of modules provided by the host (e.g. `love.*`).
+    pub externals: &'a [String],
+    pub resolver: Option<&'a ModuleResolver>,
+    pub normalizer: &'a NameNormalizer,
+}
+
+/// Classify every edge of `graph` and the modules they point to.
+///
+/// An edge is `replaced` when a replace rule rewrites that require in the requiring file;
+/// a node is `replaced` only when every require of it is rewritten. Otherwise the kind
+/// reflects where the module comes from: first-party, vendor, external or unresolved.
+pub fn classify_graph(graph: &ModuleGraph, ctx: &GraphExportCtx) -> GraphExport {
+    let externals: Vec<Pattern> = ctx
+        .externals
+        .iter()
+        .filter_map(|g| Pattern::new(g).ok())
+        .collect();
+
+    let mut nodes: BTreeMap<String, GraphNode> = BTreeMap::new();
+    let mut edges: Vec<GraphEdge> = Vec::new();
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    // Per node: (any require kept, any require rewritten).
+    let mut usage: HashMap<String, (bool, bool)> = HashMap::new();
+
+    for edge in &graph.edges {
+        let node = nodes.entry(edge.to.clone()).or_insert_with(|| {
+            let (kind, path) = source_of(graph, ctx, &externals, &edge.to, &edge.request);
+            GraphNode {
+                name: edge.to.clone(),
+                kind,
+                path,
+            }
+        });
+        let file = if edge.from == ROOT_MODULE {
+            ctx.entry_path
+        } else {
+            edge.file.as_path()
+        };
+        let replaced = find_replacement(
+            &edge.request,
+            ctx.replaces,
+            Some(file),
+            ctx.resolver,
+            ctx.normalizer,
+        )
+        .is_some();
+        let kind = if replaced {
+            ModuleKind::Replaced
+        } else {
+            node.kind
+        };
+        let used = usage.entry(edge.to.clone()).or_default();
+        if replaced {
+            used.1 = true;
+        } else {
+            used.0 = true;
+        }
+        if seen.insert((edge.from.clone(), edge.to.clone())) {
+            edges.push(GraphEdge {
+                from: edge.from.clone(),
+                to: edge.to.clone(),
+                kind,
+            });
+        }
+    }
+
+    // `--include` roots are bundled without a require pointing at them.
+    let requirers = graph.edges.iter().map(|e| &e.from).chain(&graph.roots);
+    for name in requirers {
+        if name != ROOT_MODULE && !nodes.contains_key(name) {
+            let (kind, path) = source_of(graph, ctx, &externals, name, name);
+            nodes.insert(
+                name.clone(),
+                GraphNode {
+                    name: name.clone(),
+                    kind,
+                    path,
+                },
+            );
+        }
+    }
+
+    for (name, (kept, rewritten)) in usage {
+        if rewritten && !kept {
+            if let Some(node) = nodes.get_mut(&name) {
+                node.kind = ModuleKind::Replaced;
+            }
+        }
+    }
+
+    let root = GraphNode {
+        name: ROOT_MODULE.to_string(),
+        kind: ModuleKind::FirstParty,
+        path: Some(ctx.entry_path.to_path_buf()),
+    };
+    edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+    GraphExport {
+        nodes: std::iter::once(root)
+            .chain(nodes.into_values().filter(|n| n.name != ROOT_MODULE))
+            .collect(),
+        edges,
+    }
+}
+
+fn source_of(
+    graph: &ModuleGraph,
+    ctx: &GraphExportCtx,
+    externals: &[Pattern],
+    name: &str,
+    request: &str,
+) -> (ModuleKind, Option<PathBuf>) {
+    let registered = graph.aliases.get(name).map_or(name, String::as_str);
+    if let Some(path) = graph.first_party.get(registered) {
+        return (ModuleKind::FirstParty, Some(path.clone()));
+    }
+    // The bundle emits a vendor module under any name the first-party graph does not take,
+    // and aliases of a first-party module that gave way to one lead to it.
+    if let Some(path) = ctx.vendor_mods.get(registered) {
+        return (ModuleKind::Vendor, Some(path.clone()));
+    }
+    // The same file may have been reached first under another spelling of its name.
+    if let Some(path) = ctx.resolver.and_then(|r| r.resolve(request)) {
+        return (ModuleKind::FirstParty, Some(path));
+    }
+    if let Some(path) = graph.excluded.get(name) {
+        return (ModuleKind::External, Some(path.clone()));
+    }
+    if externals.iter().any(|p| p.matches(name)) {
+        return (ModuleKind::External, None);
+    }
+    (ModuleKind::Unresolved, None)
+}
+
+/// Render a classified graph as Graphviz DOT, JSON or a Mermaid flowchart.
+pub fn render_graph(export: &GraphExport, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => render_dot(export),
+        GraphFormat::Json => {
+            let mut out = serde_json::to_string_pretty(export).unwrap_or_default();
+            out.push('\n');
+            out
+        }
+        GraphFormat::Mermaid => render_mermaid(export),
+    }
+}
+
+fn render_dot(export: &GraphExport) -> String {
+    let mut out = String::from("digraph luapack {\n  rankdir=LR;\n  node [shape=box];\n");
+    for node in &export.nodes {
+        let style = match node.kind {
+            ModuleKind::FirstParty => "",
+            ModuleKind::Vendor => ", style=filled, fillcolor=lightblue",
+            ModuleKind::Replaced => ", style=dashed",
+            ModuleKind::External => ", style=filled, fillcolor=lightgrey",
+            ModuleKind::Unresolved => ", color=red, fontcolor=red",
+        };
+        let _ = writeln!(
+            out,
+            "  {} [label={}{}];",
+            dot_quote(&node.name),
+            dot_quote(&format!("{}\n({})", node.name, node.kind.as_str())),
+            style
+        );
+    }
+    for edge in &export.edges {
+        let style = if edge.kind == ModuleKind::Replaced {
+            " [style=dashed, label=\"replaced\"]"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "  {} -> {}{};",
+            dot_quote(&edge.from),
+            dot_quote(&edge.to),
+            style
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn dot_quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+fn render_mermaid(export: &GraphExport) -> String {
+    // Module names contain dots, which Mermaid does not accept in node ids.
+    let ids: HashMap<&str, String> = export
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.name.as_str(), format!("n{}", i)))
+        .collect();
+    let mut out = String::from("flowchart LR\n");
+    for (node, id) in export
+        .nodes
+        .iter()
+        .filter_map(|n| Some((n, ids.get(n.name.as_str())?)))
+    {
+        let _ = writeln!(
+            out,
+            "  {}[\"{} ({})\"]:::{}",
+            id,
+            node.name.replace('"', "#quot;"),
+            node.kind.as_str(),
+            node.kind.as_str().replace('-', "_")
+        );
+    }
+    for edge in &export.edges {
+        let arrow = if edge.kind == ModuleKind::Replaced {
+            "-. replaced .->"
+        } else {
+            "-->"
+        };
+        let (Some(from), Some(to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str()))
+        else {
+            continue;
+        };
+        let _ = writeln!(out, "  {} {} {}", from, arrow, to);
+    }
+    out.push_str(
+        "  classDef first_party fill:#ffffff\n  classDef vendor fill:#dbeafe\n  classDef replaced stroke-dasharray:4 2\n  classDef external fill:#e5e7eb\n  classDef unresolved stroke:#dc2626,color:#dc2626\n",
+    );
+    out
+}
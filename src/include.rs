@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::normalize::normalize_module_name;
+use crate::options::NameNormalizer;
+use crate::vendor::{to_glob_and_root, vendor_raw_name};
+
+/// Modules named by `--include` globs, found under the `--path` templates, sorted by name.
+///
+/// Globs match normalized module names segment by segment: `widgets.*` matches
+/// `widgets.button` but not `widgets.form.input`, which `widgets.**` also matches.
+/// A glob that matches no module is an error, as it is most likely a typo.
+pub fn expand_includes(
+    globs: &[String],
+    templates: &[String],
+    normalizer: &NameNormalizer,
+) -> Result<Vec<(String, PathBuf)>> {
+    if globs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let modules = template_modules(templates, normalizer);
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    for g in globs {
+        let pattern = glob::Pattern::new(&g.replace('.', "/"))
+            .with_context(|| format!("bad --include glob: {g}"))?;
+        let mut matched = false;
+        for (name, path) in &modules {
+            if pattern.matches_with(&name.replace('.', "/"), options) {
+                matched = true;
+                if !out.iter().any(|(n, _)| n == name) {
+                    out.push((name.clone(), path.clone()));
+                }
+            }
+        }
+        if !matched {
+            anyhow::bail!("--include {g} matches no module under the --path templates");
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// Every file matched by the `--path` templates with its module name, in template order.
+///
+/// A file reachable through several templates is listed once, under the first.
+pub(crate) fn template_modules(
+    templates: &[String],
+    normalizer: &NameNormalizer,
+) -> Vec<(String, PathBuf)> {
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    for template in templates {
+        if !template.contains('?') {
+            continue;
+        }
+        let (glob_pat, root, init_mode) = to_glob_and_root(template);
+        let Ok(paths) = glob::glob(&glob_pat) else {
+            continue;
+        };
+        for path in paths.flatten() {
+            if !path.is_file() || !seen.insert(canonical(&path)) {
+                continue;
+            }
+            let Some(raw) = vendor_raw_name(&path, &root, init_mode) else {
+                continue;
+            };
+            out.push((normalize_module_name(&raw, normalizer), path));
+        }
+    }
+    out
+}
+
+pub(crate) fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeSet;
+
+use clap::ValueEnum;
+use full_moon::ast::{Ast, Block, LastStmt, Stmt};
+use full_moon::node::Node;
+use full_moon::tokenizer::TokenReference;
+use full_moon::visitors::VisitorMut;
+
+use crate::trivia::{append_token_trailing, prepend_leading, split_trivia, whitespace};
+
+/// Runtime instrumentation compiled into first-party modules (`--instrument`).
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum Instrument {
+    /// Count line hits per source file into `__LUAPACK_COVERAGE`.
+    Coverage,
+}
+
+/// Name of the per-module hit function the probes call.
+pub(crate) const COVERAGE_PROBE: &str = "__B_HIT";
+
+/// Insert a `__B_HIT(LINE);` probe before the first statement starting on each source line.
+///
+/// Probes go on the line of the statement they count, so line numbers are preserved.
+/// Returns the instrumented chunk and its probed lines, sorted.
+pub(crate) fn instrument_coverage_ast(ast: Ast) -> (Ast, Vec<usize>) {
+    let mut v = CoverageProbes {
+        lines: BTreeSet::new(),
+    };
+    let ast = v.visit_ast(ast);
+    (ast, v.lines.into_iter().collect())
+}
+
+struct CoverageProbes {
+    lines: BTreeSet<usize>,
+}
+
+impl CoverageProbes {
+    /// Probe for `node` if it is the first statement seen on its line.
+    fn probe(&mut self, node: &impl Node) -> Option<(Stmt, Option<TokenReference>)> {
+        let line = node.start_position()?.line();
+        if line == 0 || !self.lines.insert(line) {
+            return None;
+        }
+        let code = format!("{}({});", COVERAGE_PROBE, line);
+        let ast = full_moon::parse(&code).ok()?;
+        let (stmt, semicolon) = ast.nodes().stmts_with_semicolon().next()?.clone();
+        let semicolon = semicolon.map(|s| append_token_trailing(&s, vec![whitespace(" ")]));
+        Some((stmt, semicolon))
+    }
+}
+
+impl VisitorMut for CoverageProbes {
+    fn visit_block(&mut self, block: Block) -> Block {
+        let mut stmts: Vec<(Stmt, Option<TokenReference>)> = Vec::new();
+        for (stmt, semicolon) in block.stmts_with_semicolon().cloned() {
+            // Labels run no code; a probe after a trailing label would also break `goto`.
+            #[cfg(any(feature = "lua52", feature = "luajit"))]
+            if matches!(stmt, Stmt::Label(_)) {
+                stmts.push((stmt, semicolon));
+                continue;
+            }
+            match self.probe(&stmt) {
+                Some((probe, probe_semicolon)) => {
+                    // The statement's indentation and comments move in front of the probe.
+                    let (leading, stmt, _) = split_trivia(stmt, true, false);
+                    stmts.push((prepend_leading(probe, leading), probe_semicolon));
+                    stmts.push((stmt, semicolon));
+                }
+                None => stmts.push((stmt, semicolon)),
+            }
+        }
+        let last: Option<(LastStmt, Option<TokenReference>)> = block
+            .last_stmt_with_semicolon()
+            .cloned()
+            .map(|(last, semicolon)| match self.probe(&last) {
+                Some((probe, probe_semicolon)) => {
+                    let (leading, last, _) = split_trivia(last, true, false);
+                    stmts.push((prepend_leading(probe, leading), probe_semicolon));
+                    (last, semicolon)
+                }
+                None => (last, semicolon),
+            });
+        block.with_stmts(stmts).with_last_stmt(last)
+    }
+}
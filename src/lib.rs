@@ -1,23 +1,79 @@
 #![allow(clippy::collapsible_if)]
 mod bundle;
+mod cache;
 mod config;
+mod depfile;
+mod dialect;
+mod downlevel;
+mod explain;
 mod graph;
+mod graph_export;
+mod include;
+mod instrument;
+mod lint;
 mod normalize;
 mod options;
+mod parallel;
 mod replace;
 mod resolve;
+#[cfg(feature = "run")]
+mod run;
+mod sandbox;
 mod scan;
+mod source_map;
+mod strip_debug;
+mod strip_types;
 mod transform;
+mod trivia;
+mod unused;
+mod validate;
 mod vendor;
+mod verify;
 
 pub use bundle::BindRequire as _BindRequireExport;
-pub use bundle::{generate_bundle, lua_quote, BindRequire, BundleCtx};
-pub use config::{load_config, resolve_path_like, resolve_pathbuf, BundleConfig, LoadedConfig};
-pub use graph::ModuleGraph;
+pub use bundle::{
+    generate_bundle, generate_bundle_with_stats, generate_hot_update, lua_quote, BindRequire,
+    BundleCtx, BundleStats,
+};
+pub use cache::{BuildCache, CachedChunk, ChunkKey};
+pub use config::{
+    load_config, read_ignore_file, resolve_path_like, resolve_pathbuf, BundleConfig, LoadedConfig,
+    IGNORE_FILE,
+};
+pub use depfile::{escape_make_path, render_depfile};
+pub use dialect::{format_issues, DialectIssue, LuaDialect};
+pub use downlevel::downlevel;
+pub use explain::{
+    explain_module, format_explanation, Explanation, ModuleSource, RequireVerdicts, RuleOutcome,
+    RuleVerdict,
+};
+pub use graph::{ModuleEdge, ModuleGraph, ModuleUnit, ROOT_MODULE};
+pub use graph_export::{
+    classify_graph, render_graph, GraphEdge, GraphExport, GraphExportCtx, GraphFormat, GraphNode,
+    ModuleKind,
+};
+pub use include::expand_includes;
+pub use instrument::Instrument;
+pub use lint::{
+    lint_globals, scan_globals, stdlib_globals, GlobalUses, LintCtx, LintFile, LintSource,
+};
 pub use normalize::infer_suffixes;
 pub use options::{BundleOptions, NameNormalizer};
 pub use replace::{matches_replace, parse_replace_rules, ArgMode, MatchKind, ReplaceRule};
-pub use resolve::ModuleResolver;
-pub use scan::{find_literal_requires, RequireMatch};
+pub use resolve::{exclude_patterns, ModuleResolver};
+#[cfg(feature = "run")]
+pub use run::{run_bundle, vm_dialect, RunCtx, RUN_CHUNK_NAME};
+pub use sandbox::{parse_sandbox_allow, Sandbox, SandboxAllow, SandboxMode};
+pub use scan::{find_dynamic_requires, find_literal_requires, DynamicRequire, RequireMatch};
+pub use source_map::{ChunkSpan, SourceMap};
+pub use strip_debug::{strip_debug, Strip};
+pub use strip_types::strip_types;
 pub use transform::transform_requires;
-pub use vendor::{collect_vendor_modules, parse_vendor_specs, to_glob_and_root, VendorSpec};
+pub use unused::{find_unused_modules, UnusedModule};
+pub use validate::validate_dialect;
+pub use vendor::{
+    apply_vendor_shadows, collect_vendor_modules, collect_vendor_set, find_vendor_shadows,
+    parse_vendor_specs, to_glob_and_root, VendorConflict, VendorConflictPolicy, VendorModules,
+    VendorShadow, VendorShadowPolicy, VendorSpec,
+};
+pub use verify::{verify_bundle, BundleReport, VerifyCtx};
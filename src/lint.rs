@@ -0,0 +1,459 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use full_moon::ast::{
+    self, Block, Call, Expression, Field, FunctionArgs, FunctionBody, Index, LastStmt, Parameter,
+    Prefix, Stmt, Suffix, Var,
+};
+use full_moon::node::Node;
+use full_moon::tokenizer::{TokenReference, TokenType};
+
+use crate::dialect::{parse_lua, DialectIssue, LuaDialect};
+
+/// One chunk handed to [`lint_globals`].
+#[derive(Debug, Clone)]
+pub struct LintSource {
+    /// Registered module name; `None` for the entry chunk.
+    pub module: Option<String>,
+    pub path: PathBuf,
+    pub code: String,
+}
+
+/// What [`lint_globals`] treats as defined besides the linted chunks themselves.
+pub struct LintCtx<'a> {
+    pub dialect: LuaDialect,
+    /// Globals provided by the host (`--globals`).
+    pub host_globals: &'a [String],
+    /// Chunks whose global assignments define globals without being reported: preludes,
+    /// vendor modules and `--library` stubs.
+    pub providers: &'a [LintSource],
+}
+
+/// Issues found in one linted chunk.
+#[derive(Debug, Clone)]
+pub struct LintFile {
+    pub path: PathBuf,
+    pub issues: Vec<DialectIssue>,
+}
+
+/// Global names a chunk reads and assigns, with their positions.
+#[derive(Debug, Clone, Default)]
+pub struct GlobalUses {
+    pub reads: Vec<(String, (usize, usize))>,
+    pub writes: Vec<(String, (usize, usize))>,
+}
+
+/// Globals of the target dialect's standard library.
+pub fn stdlib_globals(dialect: LuaDialect) -> Vec<&'static str> {
+    let mut names = vec![
+        "_G",
+        "_VERSION",
+        "assert",
+        "collectgarbage",
+        "coroutine",
+        "debug",
+        "dofile",
+        "error",
+        "getmetatable",
+        "io",
+        "ipairs",
+        "load",
+        "loadfile",
+        "math",
+        "next",
+        "os",
+        "package",
+        "pairs",
+        "pcall",
+        "print",
+        "rawequal",
+        "rawget",
+        "rawset",
+        "require",
+        "select",
+        "setmetatable",
+        "string",
+        "table",
+        "tonumber",
+        "tostring",
+        "type",
+        "xpcall",
+    ];
+    match dialect {
+        LuaDialect::Lua51 => names.extend([
+            "getfenv",
+            "setfenv",
+            "loadstring",
+            "unpack",
+            "module",
+            "gcinfo",
+        ]),
+        LuaDialect::LuaJit => names.extend([
+            "getfenv",
+            "setfenv",
+            "loadstring",
+            "unpack",
+            "module",
+            "gcinfo",
+            "bit",
+            "jit",
+        ]),
+        LuaDialect::Lua52 => names.extend(["_ENV", "bit32", "rawlen", "unpack", "loadstring"]),
+        LuaDialect::Lua53 => names.extend(["_ENV", "bit32", "rawlen", "utf8"]),
+        LuaDialect::Lua54 => names.extend(["_ENV", "rawlen", "utf8", "warn"]),
+        LuaDialect::Luau => names.extend([
+            "getfenv", "setfenv", "unpack", "rawlen", "typeof", "bit32", "utf8", "buffer",
+            "vector", "task", "gcinfo",
+        ]),
+    }
+    names
+}
+
+/// Globals read and assigned by `code`, or `None` when it does not parse.
+///
+/// Names are resolved against `local` declarations, function parameters and loop variables
+/// in scope at each use; `t.x = 1` is a read of `t`, not a global assignment.
+pub fn scan_globals(code: &str) -> Option<GlobalUses> {
+    let ast = parse_lua(code).ok()?;
+    let mut scan = GlobalScan::default();
+    scan.block(ast.nodes());
+    Some(scan.uses)
+}
+
+/// Report reads of globals defined nowhere and global assignments in `sources`.
+///
+/// A global counts as defined when it is in the standard library of the target dialect, in
+/// `host_globals`, or assigned by any provider or linted chunk. Chunks that fail to parse get
+/// a single issue. Files without issues are left out.
+pub fn lint_globals(sources: &[LintSource], ctx: &LintCtx) -> Vec<LintFile> {
+    let mut defined: HashSet<String> = stdlib_globals(ctx.dialect)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    defined.extend(ctx.host_globals.iter().cloned());
+    for provider in ctx.providers {
+        if let Some(uses) = scan_globals(&provider.code) {
+            defined.extend(uses.writes.into_iter().map(|(name, _)| name));
+        }
+    }
+
+    let scanned: Vec<Option<GlobalUses>> = sources.iter().map(|s| scan_globals(&s.code)).collect();
+    for uses in scanned.iter().flatten() {
+        defined.extend(uses.writes.iter().map(|(name, _)| name.clone()));
+    }
+
+    let mut out = Vec::new();
+    for (source, uses) in sources.iter().zip(scanned) {
+        let Some(uses) = uses else {
+            out.push(LintFile {
+                path: source.path.clone(),
+                issues: vec![DialectIssue {
+                    line: 1,
+                    col: 1,
+                    message: "failed to parse; not linted".to_string(),
+                }],
+            });
+            continue;
+        };
+        let owner = match &source.module {
+            Some(name) => format!("module '{}'", name),
+            None => "the entry".to_string(),
+        };
+        let mut issues: Vec<DialectIssue> = uses
+            .reads
+            .into_iter()
+            .filter(|(name, _)| !defined.contains(name))
+            .map(|(name, (line, col))| DialectIssue {
+                line,
+                col,
+                message: format!("undefined global '{}'", name),
+            })
+            .collect();
+        issues.extend(
+            uses.writes
+                .into_iter()
+                .map(|(name, (line, col))| DialectIssue {
+                    line,
+                    col,
+                    message: format!("{} assigns global '{}'", owner, name),
+                }),
+        );
+        if issues.is_empty() {
+            continue;
+        }
+        issues.sort_by_key(|i| (i.line, i.col));
+        out.push(LintFile {
+            path: source.path.clone(),
+            issues,
+        });
+    }
+    out
+}
+
+/// Scope-aware walk; a pre-order `Visitor` cannot see where a `local` starts to apply.
+#[derive(Default)]
+struct GlobalScan {
+    scopes: Vec<HashSet<String>>,
+    uses: GlobalUses,
+}
+
+impl GlobalScan {
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes.iter().rev().any(|s| s.contains(name))
+    }
+
+    fn declare(&mut self, tok: &TokenReference) {
+        if let (Some(scope), Some(name)) = (self.scopes.last_mut(), identifier(tok)) {
+            scope.insert(name.to_string());
+        }
+    }
+
+    fn name_use(&mut self, tok: &TokenReference, write: bool) {
+        let Some(name) = identifier(tok) else {
+            return;
+        };
+        if self.is_local(name) {
+            return;
+        }
+        let entry = (name.to_string(), start_of(tok));
+        if write {
+            self.uses.writes.push(entry);
+        } else {
+            self.uses.reads.push(entry);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.scopes.push(HashSet::new());
+        self.statements(block);
+        self.scopes.pop();
+    }
+
+    /// Statements of `block` in the current scope.
+    fn statements(&mut self, block: &Block) {
+        for stmt in block.stmts() {
+            self.stmt(stmt);
+        }
+        if let Some(LastStmt::Return(ret)) = block.last_stmt() {
+            for e in ret.returns() {
+                self.expr(e);
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Assignment(a) => {
+                for e in a.expressions() {
+                    self.expr(e);
+                }
+                for v in a.variables() {
+                    self.var(v, true);
+                }
+            }
+            Stmt::Do(d) => self.block(d.block()),
+            Stmt::FunctionCall(call) => {
+                self.prefix(call.prefix());
+                for s in call.suffixes() {
+                    self.suffix(s);
+                }
+            }
+            Stmt::FunctionDeclaration(f) => {
+                let mut names = f.name().names().iter();
+                if let Some(first) = names.next() {
+                    // `function a.b()` reads `a`; only a bare name assigns a global.
+                    let bare = names.next().is_none() && f.name().method_name().is_none();
+                    self.name_use(first, bare);
+                }
+                self.function(f.body(), f.name().method_name().is_some());
+            }
+            Stmt::GenericFor(f) => {
+                for e in f.expressions() {
+                    self.expr(e);
+                }
+                self.scopes.push(HashSet::new());
+                for n in f.names() {
+                    self.declare(n);
+                }
+                self.block(f.block());
+                self.scopes.pop();
+            }
+            Stmt::If(i) => {
+                self.expr(i.condition());
+                self.block(i.block());
+                for branch in i.else_if().into_iter().flatten() {
+                    self.expr(branch.condition());
+                    self.block(branch.block());
+                }
+                if let Some(b) = i.else_block() {
+                    self.block(b);
+                }
+            }
+            Stmt::LocalAssignment(l) => {
+                for e in l.expressions() {
+                    self.expr(e);
+                }
+                for n in l.names() {
+                    self.declare(n);
+                }
+            }
+            Stmt::LocalFunction(f) => {
+                self.declare(f.name());
+                self.function(f.body(), false);
+            }
+            Stmt::NumericFor(f) => {
+                self.expr(f.start());
+                self.expr(f.end());
+                if let Some(step) = f.step() {
+                    self.expr(step);
+                }
+                self.scopes.push(HashSet::new());
+                self.declare(f.index_variable());
+                self.block(f.block());
+                self.scopes.pop();
+            }
+            Stmt::Repeat(r) => {
+                // `until` sees the locals of the loop body.
+                self.scopes.push(HashSet::new());
+                self.statements(r.block());
+                self.expr(r.until());
+                self.scopes.pop();
+            }
+            Stmt::While(w) => {
+                self.expr(w.condition());
+                self.block(w.block());
+            }
+            #[cfg(feature = "luau")]
+            Stmt::CompoundAssignment(c) => {
+                self.expr(c.rhs());
+                self.var(c.lhs(), false);
+                self.var(c.lhs(), true);
+            }
+            _ => {}
+        }
+    }
+
+    fn function(&mut self, body: &FunctionBody, method: bool) {
+        self.scopes.push(HashSet::new());
+        if method {
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.insert("self".to_string());
+            }
+        }
+        for p in body.parameters() {
+            if let Parameter::Name(tok) = p {
+                self.declare(tok);
+            }
+        }
+        self.block(body.block());
+        self.scopes.pop();
+    }
+
+    fn var(&mut self, var: &Var, write: bool) {
+        match var {
+            Var::Name(tok) => self.name_use(tok, write),
+            Var::Expression(e) => {
+                self.prefix(e.prefix());
+                for s in e.suffixes() {
+                    self.suffix(s);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn prefix(&mut self, prefix: &Prefix) {
+        match prefix {
+            Prefix::Name(tok) => self.name_use(tok, false),
+            Prefix::Expression(e) => self.expr(e),
+            _ => {}
+        }
+    }
+
+    fn suffix(&mut self, suffix: &Suffix) {
+        match suffix {
+            Suffix::Index(Index::Brackets { expression, .. }) => self.expr(expression),
+            Suffix::Call(Call::AnonymousCall(args)) => self.args(args),
+            Suffix::Call(Call::MethodCall(m)) => self.args(m.args()),
+            _ => {}
+        }
+    }
+
+    fn args(&mut self, args: &FunctionArgs) {
+        match args {
+            FunctionArgs::Parentheses { arguments, .. } => {
+                for e in arguments {
+                    self.expr(e);
+                }
+            }
+            FunctionArgs::TableConstructor(t) => self.table(t),
+            _ => {}
+        }
+    }
+
+    fn table(&mut self, table: &ast::TableConstructor) {
+        for field in table.fields() {
+            match field {
+                Field::ExpressionKey { key, value, .. } => {
+                    self.expr(key);
+                    self.expr(value);
+                }
+                Field::NameKey { value, .. } => self.expr(value),
+                Field::NoKey(value) => self.expr(value),
+                _ => {}
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::BinaryOperator { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expression::Parentheses { expression, .. }
+            | Expression::UnaryOperator { expression, .. } => self.expr(expression),
+            Expression::Function(f) => self.function(f.body(), false),
+            Expression::FunctionCall(call) => {
+                self.prefix(call.prefix());
+                for s in call.suffixes() {
+                    self.suffix(s);
+                }
+            }
+            Expression::TableConstructor(t) => self.table(t),
+            Expression::Var(v) => self.var(v, false),
+            #[cfg(feature = "luau")]
+            Expression::IfExpression(i) => {
+                self.expr(i.condition());
+                self.expr(i.if_expression());
+                for branch in i.else_if_expressions().into_iter().flatten() {
+                    self.expr(branch.condition());
+                    self.expr(branch.expression());
+                }
+                self.expr(i.else_expression());
+            }
+            #[cfg(feature = "luau")]
+            Expression::InterpolatedString(s) => {
+                for e in s.expressions() {
+                    self.expr(e);
+                }
+            }
+            #[cfg(feature = "luau")]
+            Expression::TypeAssertion { expression, .. } => self.expr(expression),
+            _ => {}
+        }
+    }
+}
+
+fn start_of(node: &impl Node) -> (usize, usize) {
+    node.start_position()
+        .map(|p| (p.line(), p.character()))
+        .unwrap_or((0, 0))
+}
+
+fn identifier(tok: &TokenReference) -> Option<&str> {
+    match tok.token().token_type() {
+        TokenType::Identifier { identifier } => Some(identifier.as_str()),
+        _ => None,
+    }
+}
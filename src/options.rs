@@ -3,7 +3,10 @@ use std::collections::HashSet;
 use std::path::PathBuf;
 
 use crate::bundle::BindRequire;
+use crate::dialect::LuaDialect;
+use crate::instrument::Instrument;
 use crate::replace::ReplaceRule;
+use crate::sandbox::Sandbox;
 use crate::vendor::VendorSpec;
 
 #[derive(Debug, Clone, Default)]
@@ -16,11 +19,25 @@ impl NameNormalizer {
         Self { suffixes }
     }
 
+    pub fn suffixes(&self) -> Vec<&str> {
+        self.suffixes.iter().map(String::as_str).collect()
+    }
+
     pub fn normalize<'a>(&self, name: &'a str) -> Cow<'a, str> {
         if self.suffixes.is_empty() {
             return Cow::Borrowed(name);
         }
+        Cow::Owned(self.strip(name).0)
+    }
+
+    /// Suffixes [`NameNormalizer::normalize`] removes from `name`, outermost first.
+    pub fn stripped_suffixes(&self, name: &str) -> Vec<String> {
+        self.strip(name).1
+    }
+
+    fn strip(&self, name: &str) -> (String, Vec<String>) {
         let mut out = name.to_string();
+        let mut stripped = Vec::new();
         loop {
             let mut changed = false;
             for suf in &self.suffixes {
@@ -28,6 +45,7 @@ impl NameNormalizer {
                 if out.ends_with(&suffixed) {
                     let newlen = out.len() - suffixed.len();
                     out.truncate(newlen);
+                    stripped.push(suf.clone());
                     changed = true;
                     break;
                 }
@@ -36,19 +54,29 @@ impl NameNormalizer {
                 break;
             }
         }
-        Cow::Owned(out)
+        (out, stripped)
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct BundleOptions {
-    pub lua: String,
+    pub lua: LuaDialect,
+    pub strip_types: bool,
+    pub strip_asserts: bool,
+    pub strip_marked: bool,
+    pub downlevel: bool,
+    pub validate: bool,
     pub paths: Vec<String>,
     pub preludes: Vec<PathBuf>,
     pub replaces: Vec<ReplaceRule>,
     pub vendor_specs: Vec<VendorSpec>,
     pub entry: Option<String>,
     pub bind: BindRequire,
+    pub runtime_trace: bool,
+    pub introspect: bool,
+    pub hot_reload: bool,
+    pub sandbox: Option<Sandbox>,
+    pub instrument: Option<Instrument>,
     pub diagnostics: bool,
     pub redact_base: Option<PathBuf>,
     pub normalizer: NameNormalizer,
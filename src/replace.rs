@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::normalize::normalize_module_name;
 use crate::options::NameNormalizer;
 use anyhow::Result;
@@ -26,6 +28,32 @@ pub struct ReplaceRule {
     pub arg: ArgMode,
 }
 
+/// Renders the rule back in `--replace` flag syntax.
+impl fmt::Display for ReplaceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.match_kind {
+            MatchKind::Exact => "exact",
+            MatchKind::Prefix => "prefix",
+            MatchKind::Path => "path",
+        };
+        write!(f, "match={},old={},new={}", kind, self.old, self.new)?;
+        if let Some(name) = &self.name {
+            write!(f, ",name={}", name)?;
+        }
+        if let Some(prefix) = &self.prefix {
+            write!(f, ",prefix={}", prefix)?;
+        }
+        for path in &self.paths {
+            write!(f, ",path={}", path)?;
+        }
+        let arg = match self.arg {
+            ArgMode::Rest => "{rest}",
+            ArgMode::Full => "{full}",
+        };
+        write!(f, ",arg={}", arg)
+    }
+}
+
 pub fn parse_replace_rules(flags: &[String]) -> Result<Vec<ReplaceRule>> {
     let mut out = Vec::new();
     for raw in flags {
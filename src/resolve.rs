@@ -1,25 +1,76 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use glob::Pattern;
 
 pub struct ModuleResolver {
     pub(crate) templates: Vec<String>,
+    /// Files never bundled, even when required (`--exclude`, `.luapackignore`).
+    excludes: Vec<Pattern>,
 }
 
 impl ModuleResolver {
     pub fn new(templates: Vec<String>) -> Self {
-        Self { templates }
+        Self {
+            templates,
+            excludes: Vec::new(),
+        }
+    }
+    /// Treat files matching `excludes` (see [`exclude_patterns`]) as external.
+    pub fn with_excludes(mut self, excludes: Vec<Pattern>) -> Self {
+        self.excludes = excludes;
+        self
     }
     pub fn dotted_to_path(name: &str) -> String {
         name.replace('.', "/")
     }
     pub fn resolve(&self, module_name: &str) -> Option<PathBuf> {
+        self.resolve_with_template(module_name).map(|(_, p)| p)
+    }
+    /// Like [`ModuleResolver::resolve`], also returning the template that matched.
+    pub fn resolve_with_template(&self, module_name: &str) -> Option<(&str, PathBuf)> {
+        self.find(module_name).filter(|(_, p)| !self.is_excluded(p))
+    }
+    /// The file and template of a module that resolves to an excluded file.
+    pub fn excluded(&self, module_name: &str) -> Option<(&str, PathBuf)> {
+        self.find(module_name).filter(|(_, p)| self.is_excluded(p))
+    }
+    /// Whether `path` matches an exclude glob; globs are matched against the absolute path.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.excludes.is_empty() {
+            return false;
+        }
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.excludes.iter().any(|p| p.matches(&path))
+    }
+    /// The first file a template finds for `module_name`, excluded or not.
+    pub(crate) fn find(&self, module_name: &str) -> Option<(&str, PathBuf)> {
         let mod_path = Self::dotted_to_path(module_name);
         for t in &self.templates {
             let candidate = t.replace('?', &mod_path);
             let p = PathBuf::from(&candidate);
             if p.exists() {
-                return Some(p);
+                return Some((t, p));
             }
         }
         None
     }
 }
+
+/// Parse exclude globs, making relative ones absolute under `base` (the current directory
+/// when `None`).
+pub fn exclude_patterns(globs: &[String], base: Option<&Path>) -> Result<Vec<Pattern>> {
+    globs
+        .iter()
+        .map(|g| {
+            let joined = match base {
+                Some(base) if Path::new(g).is_relative() => base.join(g),
+                _ => PathBuf::from(g),
+            };
+            let abs = std::path::absolute(&joined).unwrap_or(joined);
+            Pattern::new(&abs.to_string_lossy().replace('\\', "/"))
+                .with_context(|| format!("bad exclude glob: {g}"))
+        })
+        .collect()
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use mlua::{Lua, MultiValue, Value};
+
+use crate::dialect::LuaDialect;
+use crate::source_map::SourceMap;
+
+/// Chunk name the bundle is loaded under; positions in it are mapped back through the source map.
+pub const RUN_CHUNK_NAME: &str = "luapack-bundle";
+
+/// Dialect of the Lua VM compiled into this binary, selected by the `run-*` features.
+pub fn vm_dialect() -> LuaDialect {
+    if cfg!(feature = "run-luajit") {
+        LuaDialect::LuaJit
+    } else if cfg!(feature = "run-lua54") {
+        LuaDialect::Lua54
+    } else if cfg!(feature = "run-lua53") {
+        LuaDialect::Lua53
+    } else if cfg!(feature = "run-lua52") {
+        LuaDialect::Lua52
+    } else {
+        LuaDialect::Lua51
+    }
+}
+
+/// What [`run_bundle`] runs besides the bundle itself.
+pub struct RunCtx<'a> {
+    /// Script arguments, passed as `...` and as the global `arg` table.
+    pub args: &'a [String],
+    /// Lua files executed before the bundle, e.g. to define host globals.
+    pub stubs: &'a [PathBuf],
+    /// Layout of the bundle, used to map error positions to the original files.
+    pub source_map: &'a SourceMap,
+}
+
+/// Run a generated bundle in the embedded Lua VM.
+///
+/// Runtime errors are returned with every bundle position (`luapack-bundle:LINE:`) rewritten
+/// to the file and line it was generated from.
+pub fn run_bundle(code: &str, ctx: &RunCtx) -> Result<()> {
+    let lua = Lua::new();
+    let map_err = |e: mlua::Error| {
+        let text = match e {
+            mlua::Error::RuntimeError(msg) | mlua::Error::SyntaxError { message: msg, .. } => msg,
+            other => other.to_string(),
+        };
+        anyhow::anyhow!("{}", ctx.source_map.rewrite(RUN_CHUNK_NAME, &text))
+    };
+
+    let arg = lua.create_table().map_err(map_err)?;
+    arg.set(0, RUN_CHUNK_NAME).map_err(map_err)?;
+    for (i, a) in ctx.args.iter().enumerate() {
+        arg.set(i + 1, a.as_str()).map_err(map_err)?;
+    }
+    lua.globals().set("arg", arg).map_err(map_err)?;
+
+    for stub in ctx.stubs {
+        let src = fs::read_to_string(stub)
+            .with_context(|| format!("failed to read stubs: {}", stub.display()))?;
+        lua.load(&src)
+            .set_name(format!("@{}", stub.display()))
+            .exec()
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| format!("failed to run stubs: {}", stub.display()))?;
+    }
+
+    let args: MultiValue = ctx
+        .args
+        .iter()
+        .map(|a| lua.create_string(a).map(Value::String))
+        .collect::<mlua::Result<_>>()
+        .map_err(map_err)?;
+    lua.load(code)
+        .set_name(format!("={}", RUN_CHUNK_NAME))
+        .call::<_, MultiValue>(args)
+        .map_err(map_err)?;
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use clap::ValueEnum;
+
+/// What a sandboxed module's global assignment does (`--sandbox`).
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum SandboxMode {
+    /// Raise an error at the assignment.
+    Error,
+    /// Log a warning once per module and global, then assign as usual.
+    Warn,
+}
+
+/// Globals that modules under `prefix` may assign (`--sandbox-allow`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxAllow {
+    /// Module-name prefix; empty for every module.
+    pub prefix: String,
+    pub globals: Vec<String>,
+}
+
+/// Per-module environments for the bundle runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandbox {
+    pub mode: SandboxMode,
+    pub allow: Vec<SandboxAllow>,
+}
+
+/// Parse `--sandbox-allow` values of the form `PREFIX=GLOBAL[,GLOBAL...]`, where a
+/// `PREFIX` of `*` matches every module.
+pub fn parse_sandbox_allow(flags: &[String]) -> Result<Vec<SandboxAllow>> {
+    let mut out = Vec::new();
+    for raw in flags {
+        let Some((prefix, globals)) = raw.split_once('=') else {
+            anyhow::bail!(
+                "sandbox allow rule needs PREFIX=GLOBAL[,GLOBAL...]: {}",
+                raw
+            );
+        };
+        let prefix = match prefix.trim() {
+            "*" => String::new(),
+            p => p.to_string(),
+        };
+        let globals: Vec<String> = globals
+            .split(',')
+            .map(str::trim)
+            .filter(|g| !g.is_empty())
+            .map(str::to_string)
+            .collect();
+        if globals.is_empty() {
+            anyhow::bail!("sandbox allow rule lists no globals: {}", raw);
+        }
+        out.push(SandboxAllow { prefix, globals });
+    }
+    Ok(out)
+}
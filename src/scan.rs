@@ -1,10 +1,14 @@
 use std::collections::HashSet;
 
-use full_moon::ast::{self, Expression, FunctionArgs, FunctionCall, Prefix, Suffix};
-use full_moon::tokenizer::TokenType;
-use full_moon::visitors::VisitorMut;
+use full_moon::ast::{self, Ast, Expression, FunctionArgs, FunctionCall, Prefix, Suffix};
+use full_moon::node::Node;
+use full_moon::tokenizer::{TokenReference, TokenType};
+use full_moon::visitors::Visitor;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::dialect::parse_lua;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequireMatch {
     pub module: String,
     pub line: usize,
@@ -12,13 +16,64 @@ pub struct RequireMatch {
 }
 
 pub fn find_literal_requires(code: &str) -> Vec<RequireMatch> {
-    let ast = match full_moon::parse(code) {
-        Ok(a) => a,
-        Err(_) => return Vec::new(),
-    };
+    match parse_lua(code) {
+        Ok(ast) => requires_in_ast(&ast),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// `require` calls whose module name is computed at run time, e.g. `require("widgets." .. name)`.
+///
+/// The graph cannot follow these; `--include` names the modules they may load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicRequire {
+    /// Source text of the argument.
+    pub argument: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+pub fn find_dynamic_requires(code: &str) -> Vec<DynamicRequire> {
+    match parse_lua(code) {
+        Ok(ast) => scan_requires(&ast).1,
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Comment that stops the `require` calls on its line from being followed or rewritten.
+pub(crate) const IGNORE_MARKER: &str = "luapack: ignore";
+
+/// Lines carrying a `-- luapack: ignore` comment.
+pub(crate) fn ignored_lines(ast: &Ast) -> HashSet<usize> {
+    ast.nodes()
+        .tokens()
+        .chain(std::iter::once(ast.eof()))
+        .flat_map(|t| t.leading_trivia().chain(t.trailing_trivia()))
+        .filter(|t| {
+            matches!(t.token_type(), TokenType::SingleLineComment { comment } if comment.trim() == IGNORE_MARKER)
+        })
+        .map(|t| t.start_position().line())
+        .collect()
+}
+
+/// Whether `node` spans a line in `ignored` (see [`ignored_lines`]).
+pub(crate) fn is_ignored(node: &impl Node, ignored: &HashSet<usize>) -> bool {
+    match (node.start_position(), node.end_position()) {
+        (Some(start), Some(end)) => (start.line()..=end.line()).any(|l| ignored.contains(&l)),
+        _ => false,
+    }
+}
 
+/// Literal `require` calls in an already parsed chunk.
+pub(crate) fn requires_in_ast(ast: &Ast) -> Vec<RequireMatch> {
+    scan_requires(ast).0
+}
+
+fn scan_requires(ast: &Ast) -> (Vec<RequireMatch>, Vec<DynamicRequire>) {
     struct Collect<'a> {
         found: Vec<RequireMatch>,
+        dynamic: Vec<DynamicRequire>,
+        ignored: HashSet<usize>,
         scope_stack: Vec<HashSet<String>>,
         _p: std::marker::PhantomData<&'a ()>,
     }
@@ -38,33 +93,26 @@ pub fn find_literal_requires(code: &str) -> Vec<RequireMatch> {
             }
         }
     }
-    impl<'a> VisitorMut for Collect<'a> {
-        fn visit_block(&mut self, node: ast::Block) -> ast::Block {
+    impl<'a> Visitor for Collect<'a> {
+        fn visit_block(&mut self, _: &ast::Block) {
             self.push();
-            node
         }
-        fn visit_block_end(&mut self, node: ast::Block) -> ast::Block {
+        fn visit_block_end(&mut self, _: &ast::Block) {
             self.pop();
-            node
         }
-        fn visit_local_assignment(&mut self, node: ast::LocalAssignment) -> ast::LocalAssignment {
+        fn visit_local_assignment(&mut self, node: &ast::LocalAssignment) {
             for n in node.names().iter() {
                 if let TokenType::Identifier { identifier } = n.token().token_type() {
                     self.declare(identifier.as_str());
                 }
             }
-            node
         }
-        fn visit_local_function(&mut self, node: ast::LocalFunction) -> ast::LocalFunction {
+        fn visit_local_function(&mut self, node: &ast::LocalFunction) {
             if let TokenType::Identifier { identifier } = node.name().token().token_type() {
                 self.declare(identifier.as_str());
             }
-            node
         }
-        fn visit_anonymous_function(
-            &mut self,
-            node: ast::AnonymousFunction,
-        ) -> ast::AnonymousFunction {
+        fn visit_anonymous_function(&mut self, node: &ast::AnonymousFunction) {
             self.push();
             for p in node.body().parameters().iter() {
                 if let ast::Parameter::Name(tok) = p {
@@ -73,69 +121,63 @@ pub fn find_literal_requires(code: &str) -> Vec<RequireMatch> {
                     }
                 }
             }
-            node
         }
-        fn visit_anonymous_function_end(
-            &mut self,
-            node: ast::AnonymousFunction,
-        ) -> ast::AnonymousFunction {
+        fn visit_anonymous_function_end(&mut self, _: &ast::AnonymousFunction) {
             self.pop();
-            node
         }
-        fn visit_function_call(&mut self, node: FunctionCall) -> FunctionCall {
-            if let Prefix::Name(tok) = node.prefix() {
-                if matches!(tok.token().token_type(), TokenType::Identifier { identifier } if identifier.as_str() == "require")
-                    && !self.in_scope("require")
-                {
-                    if let Some(Suffix::Call(ast::Call::AnonymousCall(args))) =
-                        node.suffixes().next()
-                    {
-                        match args {
-                            FunctionArgs::Parentheses { arguments, .. } => {
-                                let mut it = arguments.iter();
-                                if let Some(Expression::String(_)) = it.next() {
-                                    if it.next().is_none() {
-                                        if let Some(Expression::String(tokref)) =
-                                            arguments.iter().next()
-                                        {
-                                            if let TokenType::StringLiteral { literal, .. } =
-                                                tokref.token().token_type()
-                                            {
-                                                self.found.push(RequireMatch {
-                                                    module: literal.to_string(),
-                                                    line: 0,
-                                                    col: 0,
-                                                });
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            FunctionArgs::String(tokref) => {
-                                if let TokenType::StringLiteral { literal, .. } =
-                                    tokref.token().token_type()
-                                {
-                                    self.found.push(RequireMatch {
-                                        module: literal.to_string(),
-                                        line: 0,
-                                        col: 0,
-                                    });
-                                }
-                            }
-                            _ => {}
+        fn visit_function_call(&mut self, node: &FunctionCall) {
+            let Prefix::Name(tok) = node.prefix() else {
+                return;
+            };
+            if !matches!(tok.token().token_type(), TokenType::Identifier { identifier } if identifier.as_str() == "require")
+                || self.in_scope("require")
+                || is_ignored(node, &self.ignored)
+            {
+                return;
+            }
+            let Some(Suffix::Call(ast::Call::AnonymousCall(args))) = node.suffixes().next() else {
+                return;
+            };
+            let (line, col) = node
+                .start_position()
+                .map(|p| (p.line(), p.character()))
+                .unwrap_or((0, 0));
+            let literal = |tokref: &TokenReference| match tokref.token().token_type() {
+                TokenType::StringLiteral { literal, .. } => Some(literal.to_string()),
+                _ => None,
+            };
+            let module = match args {
+                FunctionArgs::Parentheses { arguments, .. } => {
+                    let mut it = arguments.iter();
+                    match (it.next(), it.next()) {
+                        (Some(Expression::String(tokref)), None) => literal(tokref),
+                        (Some(Expression::String(_)), Some(_)) | (None, _) => None,
+                        (Some(expr), _) => {
+                            self.dynamic.push(DynamicRequire {
+                                argument: expr.to_string().trim().to_string(),
+                                line,
+                                col,
+                            });
+                            None
                         }
                     }
                 }
+                FunctionArgs::String(tokref) => literal(tokref),
+                _ => None,
+            };
+            if let Some(module) = module {
+                self.found.push(RequireMatch { module, line, col });
             }
-            node
         }
     }
 
     let mut v = Collect {
         found: Vec::new(),
+        dynamic: Vec::new(),
+        ignored: ignored_lines(ast),
         scope_stack: vec![Default::default()],
         _p: std::marker::PhantomData,
     };
-    let _ = v.visit_ast(ast);
-    v.found
+    v.visit_ast(ast);
+    (v.found, v.dynamic)
 }
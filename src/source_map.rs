@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+
+use regex::{Captures, Regex};
+
+/// Where the code of one source file sits in a generated bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSpan {
+    /// Registered module name; `None` for preludes.
+    pub module: Option<String>,
+    pub path: PathBuf,
+    /// Bundle line (from 1) holding the first line of the file.
+    pub first_line: usize,
+    pub line_count: usize,
+}
+
+/// Bundle lines back to the files they came from, built from the bundle's module layout.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    pub chunks: Vec<ChunkSpan>,
+}
+
+impl SourceMap {
+    /// File and line (from 1) that bundle line `line` was generated from.
+    pub fn locate(&self, line: usize) -> Option<(&ChunkSpan, usize)> {
+        self.chunks
+            .iter()
+            .find(|c| line >= c.first_line && line < c.first_line + c.line_count)
+            .map(|c| (c, line - c.first_line + 1))
+    }
+
+    /// Rewrite every `chunk:LINE:` (and `<chunk:LINE>`) position in an error message or
+    /// traceback to the original `path:LINE`, where `chunk` is the name the bundle was
+    /// loaded under.
+    pub fn rewrite(&self, chunk: &str, text: &str) -> String {
+        let pattern = format!(r"{}:(\d+)([:>])", regex::escape(chunk));
+        let Ok(re) = Regex::new(&pattern) else {
+            return text.to_string();
+        };
+        re.replace_all(text, |caps: &Captures| {
+            let line: usize = caps[1].parse().unwrap_or(0);
+            match self.locate(line) {
+                Some((span, line)) => format!("{}:{}{}", display_path(&span.path), line, &caps[2]),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+    }
+}
+
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
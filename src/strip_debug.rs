@@ -0,0 +1,202 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use full_moon::ast::{self, Block, Expression, Prefix, Stmt, Suffix};
+use full_moon::node::Node;
+use full_moon::tokenizer::{Token, TokenType};
+use full_moon::visitors::Visitor;
+
+use crate::dialect::parse_lua;
+
+/// Statements removed by `--strip`.
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq)]
+pub enum Strip {
+    /// `assert(...)` calls used as statements.
+    Assert,
+}
+
+const DEBUG_BEGIN: &str = "@debug begin";
+const DEBUG_END: &str = "@debug end";
+
+/// Remove `assert(...)` statements (`asserts`) and `--@debug begin` ... `--@debug end`
+/// regions (`marked`) from a chunk.
+///
+/// Removed code is replaced by its line breaks, so every other token, comment and line
+/// number stays where it was. Returns the code and the number of removed asserts and
+/// regions. Code that fails to parse is returned unchanged; unbalanced markers, or a region
+/// that leaves invalid code behind, are errors.
+pub fn strip_debug(code: &str, asserts: bool, marked: bool) -> Result<(String, usize)> {
+    if !asserts && !marked {
+        return Ok((code.to_string(), 0));
+    }
+    let Ok(ast) = parse_lua(code) else {
+        return Ok((code.to_string(), 0));
+    };
+
+    let mut cuts: Vec<Cut> = Vec::new();
+    if marked {
+        // Comments after the last statement hang off the end-of-file token.
+        let trivia = ast
+            .nodes()
+            .tokens()
+            .chain(std::iter::once(ast.eof()))
+            .flat_map(|t| {
+                t.leading_trivia()
+                    .chain(std::iter::once(t.token()))
+                    .chain(t.trailing_trivia())
+            });
+        cuts.extend(marked_regions(trivia)?);
+    }
+    if asserts {
+        let mut finder = AssertFinder::default();
+        finder.visit_ast(&ast);
+        cuts.extend(finder.cuts);
+    }
+    if cuts.is_empty() {
+        return Ok((code.to_string(), 0));
+    }
+
+    // Asserts inside a removed region are not counted twice.
+    cuts.sort_by_key(|c| (c.range.start, std::cmp::Reverse(c.range.end)));
+    let mut out = String::with_capacity(code.len());
+    let mut at = 0;
+    let mut removed = 0;
+    for cut in cuts {
+        if cut.range.start < at {
+            continue;
+        }
+        out.push_str(&code[at..cut.range.start]);
+        out.push_str(&"\n".repeat(code[cut.range.clone()].matches('\n').count()));
+        if cut.separate {
+            out.push(';');
+        }
+        at = cut.range.end;
+        removed += 1;
+    }
+    out.push_str(&code[at..]);
+
+    if parse_lua(&out).is_err() {
+        match (asserts, marked) {
+            (true, false) => anyhow::bail!("removing assert statements leaves invalid Lua"),
+            (false, true) => anyhow::bail!(
+                "removing --@debug regions leaves invalid Lua; does a region cut a statement in two?"
+            ),
+            _ => anyhow::bail!(
+                "removing assert statements and --@debug regions leaves invalid Lua; \
+                 does a region cut a statement in two?"
+            ),
+        }
+    }
+    Ok((out, removed))
+}
+
+/// Bytes to remove; `separate` keeps a `;` so the next statement, starting with `(`, is not
+/// read as a call of the previous one.
+struct Cut {
+    range: Range<usize>,
+    separate: bool,
+}
+
+/// Byte ranges of `--@debug begin` ... `--@debug end`, markers included. Regions may nest.
+fn marked_regions<'a>(tokens: impl Iterator<Item = &'a Token>) -> Result<Vec<Cut>> {
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut cuts = Vec::new();
+    for tok in tokens {
+        let TokenType::SingleLineComment { comment } = tok.token_type() else {
+            continue;
+        };
+        let start = tok.start_position();
+        match comment.trim() {
+            DEBUG_BEGIN => open.push((start.bytes(), start.line())),
+            DEBUG_END => {
+                let Some((begin, _)) = open.pop() else {
+                    anyhow::bail!(
+                        "line {}: --{} without --{}",
+                        start.line(),
+                        DEBUG_END,
+                        DEBUG_BEGIN
+                    );
+                };
+                if open.is_empty() {
+                    cuts.push(Cut {
+                        range: begin..tok.end_position().bytes(),
+                        separate: false,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((_, line)) = open.first() {
+        anyhow::bail!("line {}: --{} without --{}", line, DEBUG_BEGIN, DEBUG_END);
+    }
+    Ok(cuts)
+}
+
+#[derive(Default)]
+struct AssertFinder {
+    cuts: Vec<Cut>,
+}
+
+impl Visitor for AssertFinder {
+    fn visit_block(&mut self, block: &Block) {
+        let stmts: Vec<_> = block.stmts_with_semicolon().collect();
+        // Whether the last kept statement could run into a following `(`: it exists and
+        // has no `;` of its own (Lua 5.1 rejects `;;`).
+        let mut open_before = false;
+        for (i, (stmt, semicolon)) in stmts.iter().enumerate() {
+            let kept = !is_assert_stmt(stmt);
+            let (Some(start), Some(end)) = (stmt.start_position(), stmt.end_position()) else {
+                open_before = semicolon.is_none();
+                continue;
+            };
+            if kept {
+                open_before = semicolon.is_none();
+                continue;
+            }
+            let end = semicolon
+                .as_ref()
+                .and_then(|s| s.end_position())
+                .unwrap_or(end);
+            let next_is_paren = match stmts.get(i + 1) {
+                Some((next, _)) => starts_with_paren(next),
+                None => false,
+            };
+            self.cuts.push(Cut {
+                range: start.bytes()..end.bytes(),
+                separate: open_before && next_is_paren,
+            });
+        }
+    }
+}
+
+/// `assert(...)` / `assert "..."` as a statement; `assert(f)()` uses the result and stays.
+fn is_assert_stmt(stmt: &Stmt) -> bool {
+    let Stmt::FunctionCall(call) = stmt else {
+        return false;
+    };
+    let Prefix::Name(name) = call.prefix() else {
+        return false;
+    };
+    let mut suffixes = call.suffixes();
+    matches!(
+        name.token().token_type(),
+        TokenType::Identifier { identifier } if identifier.as_str() == "assert"
+    ) && matches!(
+        suffixes.next(),
+        Some(Suffix::Call(ast::Call::AnonymousCall(_)))
+    ) && suffixes.next().is_none()
+}
+
+fn starts_with_paren(stmt: &Stmt) -> bool {
+    let prefix = match stmt {
+        Stmt::FunctionCall(call) => call.prefix(),
+        Stmt::Assignment(a) => match a.variables().iter().next() {
+            Some(ast::Var::Expression(e)) => e.prefix(),
+            _ => return false,
+        },
+        _ => return false,
+    };
+    matches!(prefix, Prefix::Expression(e) if matches!(**e, Expression::Parentheses { .. }))
+}
@@ -0,0 +1,222 @@
+use full_moon::ast::Ast;
+
+/// Remove Luau type annotations, `type`/`export type` declarations, generics and `::` casts
+/// so typed sources run on plain Lua 5.1.
+///
+/// Line numbers are preserved: removed multi-line declarations leave their line breaks behind.
+/// Returns the stripped code and the number of removed type constructs. Code that fails to
+/// parse, or any code when built without the `luau` feature, is returned unchanged.
+pub fn strip_types(code: &str) -> (String, usize) {
+    if !cfg!(feature = "luau") {
+        return (code.to_string(), 0);
+    }
+    match crate::dialect::parse_lua(code) {
+        Ok(ast) => {
+            let (ast, removed) = strip_types_ast(ast);
+            (ast.to_string(), removed)
+        }
+        Err(_) => (code.to_string(), 0),
+    }
+}
+
+/// [`strip_types`] for an already parsed chunk.
+pub(crate) fn strip_types_ast(ast: Ast) -> (Ast, usize) {
+    #[cfg(feature = "luau")]
+    {
+        luau::strip(ast)
+    }
+    #[cfg(not(feature = "luau"))]
+    {
+        (ast, 0)
+    }
+}
+
+#[cfg(feature = "luau")]
+mod luau {
+    use full_moon::ast::luau::TypeSpecifier;
+    use full_moon::ast::punctuated::Punctuated;
+    use full_moon::ast::span::ContainedSpan;
+    use full_moon::ast::{self, Ast, Block, Expression, Parameter, Stmt};
+    use full_moon::node::Node;
+    use full_moon::tokenizer::{Token, TokenReference};
+    use full_moon::visitors::VisitorMut;
+
+    use crate::trivia::{
+        append_token_trailing, append_trailing, line_breaks_of, prepend_leading,
+        significant_trivia, split_trivia, whitespace,
+    };
+
+    pub(super) fn strip(ast: Ast) -> (Ast, usize) {
+        let mut v = TypeStripper { removed: 0 };
+        let new_ast = v.visit_ast(ast);
+        (new_ast, v.removed)
+    }
+
+    struct TypeStripper {
+        removed: usize,
+    }
+
+    /// Trivia that replaces a removed annotation: its line breaks and trailing trivia.
+    fn carry(node: &impl Node) -> Vec<Token> {
+        let (_, trailing) = node.surrounding_trivia();
+        let trailing: Vec<Token> = trailing.into_iter().cloned().collect();
+        let breaks = |s: String| s.matches('\n').count();
+        let trailing_breaks: usize = trailing.iter().map(|t| breaks(t.to_string())).sum();
+        let total: usize = node.tokens().map(|t| breaks(t.to_string())).sum();
+        let mut out = Vec::new();
+        if total > trailing_breaks {
+            out.push(whitespace(&"\n".repeat(total - trailing_breaks)));
+        }
+        out.extend(trailing);
+        out
+    }
+
+    fn is_type_stmt(stmt: &Stmt) -> bool {
+        matches!(
+            stmt,
+            Stmt::TypeDeclaration(_)
+                | Stmt::ExportedTypeDeclaration(_)
+                | Stmt::TypeFunction(_)
+                | Stmt::ExportedTypeFunction(_)
+        )
+    }
+
+    impl TypeStripper {
+        fn strip_names(
+            &mut self,
+            names: Punctuated<TokenReference>,
+            specs: &[Option<TypeSpecifier>],
+        ) -> Punctuated<TokenReference> {
+            names
+                .into_pairs()
+                .enumerate()
+                .map(|(i, pair)| match specs.get(i).and_then(Option::as_ref) {
+                    Some(spec) => {
+                        self.removed += 1;
+                        pair.map(|tok| append_token_trailing(&tok, carry(spec)))
+                    }
+                    None => pair,
+                })
+                .collect()
+        }
+    }
+
+    impl VisitorMut for TypeStripper {
+        fn visit_local_assignment(&mut self, node: ast::LocalAssignment) -> ast::LocalAssignment {
+            let specs: Vec<Option<TypeSpecifier>> =
+                node.type_specifiers().map(|s| s.cloned()).collect();
+            if specs.iter().all(Option::is_none) {
+                return node;
+            }
+            let names = self.strip_names(node.names().clone(), &specs);
+            node.with_names(names).with_type_specifiers(Vec::new())
+        }
+
+        fn visit_generic_for(&mut self, node: ast::GenericFor) -> ast::GenericFor {
+            let specs: Vec<Option<TypeSpecifier>> =
+                node.type_specifiers().map(|s| s.cloned()).collect();
+            if specs.iter().all(Option::is_none) {
+                return node;
+            }
+            let names = self.strip_names(node.names().clone(), &specs);
+            node.with_names(names).with_type_specifiers(Vec::new())
+        }
+
+        fn visit_numeric_for(&mut self, node: ast::NumericFor) -> ast::NumericFor {
+            let Some(spec) = node.type_specifier().cloned() else {
+                return node;
+            };
+            self.removed += 1;
+            let var = append_token_trailing(node.index_variable(), carry(&spec));
+            node.with_index_variable(var).with_type_specifier(None)
+        }
+
+        fn visit_function_body(&mut self, node: ast::FunctionBody) -> ast::FunctionBody {
+            let specs: Vec<Option<TypeSpecifier>> =
+                node.type_specifiers().map(|s| s.cloned()).collect();
+            let mut node = node;
+            if node.generics().is_some() {
+                self.removed += 1;
+                node = node.with_generics(None);
+            }
+            if specs.iter().any(Option::is_some) {
+                let params = node
+                    .parameters()
+                    .clone()
+                    .into_pairs()
+                    .enumerate()
+                    .map(|(i, pair)| match specs.get(i).and_then(Option::as_ref) {
+                        Some(spec) => {
+                            self.removed += 1;
+                            pair.map(|p| match p {
+                                Parameter::Name(tok) => {
+                                    Parameter::Name(append_token_trailing(&tok, carry(spec)))
+                                }
+                                Parameter::Ellipsis(tok) => {
+                                    Parameter::Ellipsis(append_token_trailing(&tok, carry(spec)))
+                                }
+                                other => other,
+                            })
+                        }
+                        None => pair,
+                    })
+                    .collect();
+                node = node
+                    .with_parameters(params)
+                    .with_type_specifiers(Vec::new());
+            }
+            if let Some(ret) = node.return_type().cloned() {
+                self.removed += 1;
+                let (open, close) = node.parameters_parentheses().tokens();
+                let parens =
+                    ContainedSpan::new(open.clone(), append_token_trailing(close, carry(&ret)));
+                node = node
+                    .with_parameters_parentheses(parens)
+                    .with_return_type(None);
+            }
+            node
+        }
+
+        fn visit_expression_end(&mut self, node: Expression) -> Expression {
+            match node {
+                Expression::TypeAssertion {
+                    expression,
+                    type_assertion,
+                } => {
+                    self.removed += 1;
+                    let (_, expression, gap) = split_trivia(*expression, false, true);
+                    let mut trailing = significant_trivia(gap);
+                    trailing.extend(carry(&type_assertion));
+                    append_trailing(expression, trailing)
+                }
+                other => other,
+            }
+        }
+
+        fn visit_block_end(&mut self, node: Block) -> Block {
+            if !node.stmts().any(is_type_stmt) {
+                return node;
+            }
+            let mut stmts = Vec::new();
+            let mut pending: Vec<Token> = Vec::new();
+            for (stmt, semi) in node.stmts_with_semicolon().cloned() {
+                if is_type_stmt(&stmt) {
+                    self.removed += 1;
+                    pending.extend(line_breaks_of(&stmt));
+                    continue;
+                }
+                let stmt = prepend_leading(stmt, std::mem::take(&mut pending));
+                stmts.push((stmt, semi));
+            }
+            let mut last = node.last_stmt_with_semicolon().cloned();
+            if !pending.is_empty() {
+                if let Some((stmt, semi)) = last.take() {
+                    last = Some((prepend_leading(stmt, pending), semi));
+                } else if let Some((stmt, semi)) = stmts.pop() {
+                    stmts.push((append_trailing(stmt, pending), semi));
+                }
+            }
+            node.with_stmts(stmts).with_last_stmt(last)
+        }
+    }
+}
@@ -1,17 +1,19 @@
 use std::collections::HashSet;
 use std::path::Path;
 
-use full_moon::ast::{self, Expression, FunctionArgs, FunctionCall, Prefix, Suffix};
+use full_moon::ast::{self, Ast, Expression, FunctionArgs, FunctionCall, Prefix, Suffix};
 use full_moon::tokenizer::{StringLiteralQuoteType, Token, TokenReference, TokenType};
 use full_moon::visitors::VisitorMut;
 use glob::Pattern;
 
+use crate::dialect::parse_lua;
 use crate::normalize::normalize_module_name;
 use crate::options::NameNormalizer;
 use crate::replace::{ArgMode, MatchKind, ReplaceRule};
 use crate::resolve::ModuleResolver;
+use crate::scan::{ignored_lines, is_ignored};
 
-fn rule_applies_to_file(rule: &ReplaceRule, file: &Path) -> bool {
+pub(crate) fn rule_applies_to_file(rule: &ReplaceRule, file: &Path) -> bool {
     if rule.paths.is_empty() {
         return true;
     }
@@ -28,7 +30,7 @@ fn rule_applies_to_file(rule: &ReplaceRule, file: &Path) -> bool {
     false
 }
 
-fn apply_replace(
+pub(crate) fn apply_replace(
     module: &str,
     r: &ReplaceRule,
     resolver: Option<&ModuleResolver>,
@@ -61,7 +63,8 @@ fn apply_replace(
         }
         MatchKind::Path => {
             if let Some(res) = resolver {
-                if let Some(path) = res.resolve(&module_n) {
+                // Excluded files can still be routed to a loader.
+                if let Some((_, path)) = res.find(&module_n) {
                     let path_str = path.to_string_lossy().replace('\\', "/");
                     for pat in &r.paths {
                         if let Ok(p) = Pattern::new(pat) {
@@ -87,6 +90,24 @@ fn apply_replace(
     None
 }
 
+/// The first rule that rewrites `require(module)` inside `file`: its index in `rules`,
+/// the new callee and the new argument.
+pub(crate) fn find_replacement(
+    module: &str,
+    rules: &[ReplaceRule],
+    file: Option<&Path>,
+    resolver: Option<&ModuleResolver>,
+    normalizer: &NameNormalizer,
+) -> Option<(usize, String, String)> {
+    rules.iter().enumerate().find_map(|(i, r)| {
+        if r.match_kind != MatchKind::Path && !file.is_none_or(|f| rule_applies_to_file(r, f)) {
+            return None;
+        }
+        let (callee, arg) = apply_replace(module, r, resolver, normalizer)?;
+        Some((i, callee, arg))
+    })
+}
+
 pub fn transform_requires(
     code: &str,
     rules: &[ReplaceRule],
@@ -97,12 +118,26 @@ pub fn transform_requires(
     if rules.is_empty() {
         return (code.to_string(), 0);
     }
-    let ast = match full_moon::parse(code) {
-        Ok(ast) => ast,
-        Err(_) => {
-            return (code.to_string(), 0);
+    match parse_lua(code) {
+        Ok(ast) => {
+            let (new_ast, rewrites) = transform_ast(ast, rules, file_path, resolver, normalizer);
+            (new_ast.to_string(), rewrites)
         }
-    };
+        Err(_) => (code.to_string(), 0),
+    }
+}
+
+/// Apply the replace rules to an already parsed chunk.
+pub(crate) fn transform_ast(
+    ast: Ast,
+    rules: &[ReplaceRule],
+    file_path: Option<&Path>,
+    resolver: Option<&ModuleResolver>,
+    normalizer: &NameNormalizer,
+) -> (Ast, usize) {
+    if rules.is_empty() {
+        return (ast, 0);
+    }
 
     struct RequireRewriter<'a> {
         rules: &'a [ReplaceRule],
@@ -111,6 +146,7 @@ pub fn transform_requires(
         rewrites: usize,
         resolver: Option<&'a ModuleResolver>,
         normalizer: &'a NameNormalizer,
+        ignored: HashSet<usize>,
     }
     impl<'a> full_moon::visitors::VisitorMut for RequireRewriter<'a> {
         fn visit_block(&mut self, node: ast::Block) -> ast::Block {
@@ -175,7 +211,7 @@ pub fn transform_requires(
                 }
                 _ => false,
             };
-            if !is_global_require {
+            if !is_global_require || is_ignored(&new_node, &self.ignored) {
                 return new_node;
             }
 
@@ -230,19 +266,13 @@ pub fn transform_requires(
                 return new_node;
             };
 
-            let mut replaced: Option<(String, String)> = None;
-            for r in self.rules {
-                if r.match_kind != MatchKind::Path && !self.path_rule_allows(r) {
-                    continue;
-                }
-                if let Some((new_callee, new_arg)) =
-                    apply_replace(&module_name, r, self.resolver, self.normalizer)
-                {
-                    replaced = Some((new_callee, new_arg));
-                    break;
-                }
-            }
-            let Some((new_callee, new_arg)) = replaced else {
+            let Some((_, new_callee, new_arg)) = find_replacement(
+                &module_name,
+                self.rules,
+                self.file,
+                self.resolver,
+                self.normalizer,
+            ) else {
                 return new_node;
             };
 
@@ -320,15 +350,9 @@ pub fn transform_requires(
             });
             keep_trivia_from.with_token(t)
         }
-        fn path_rule_allows(&self, rule: &ReplaceRule) -> bool {
-            if let Some(f) = self.file {
-                rule_applies_to_file(rule, f)
-            } else {
-                true
-            }
-        }
     }
 
+    let ignored = ignored_lines(&ast);
     let mut v = RequireRewriter {
         rules,
         file: file_path,
@@ -336,7 +360,8 @@ pub fn transform_requires(
         rewrites: 0,
         resolver,
         normalizer,
+        ignored,
     };
     let new_ast = v.visit_ast(ast);
-    (new_ast.to_string(), v.rewrites)
+    (new_ast, v.rewrites)
 }
@@ -0,0 +1,151 @@
+//! Helpers for moving whitespace and comments around when nodes are rewritten or removed.
+#![cfg_attr(
+    not(feature = "luau"),
+    expect(
+        dead_code,
+        reason = "some helpers are only needed by the Luau type stripper"
+    )
+)]
+
+use full_moon::node::Node;
+use full_moon::tokenizer::{Token, TokenReference, TokenType};
+use full_moon::visitors::{VisitMut, VisitorMut};
+
+/// Apply `first` to the first token and `last` to the last token of `node`.
+pub(crate) fn map_edge_tokens<N, F, L>(node: N, first: F, last: L) -> N
+where
+    N: Node + VisitMut,
+    F: FnOnce(TokenReference) -> TokenReference,
+    L: FnOnce(TokenReference) -> TokenReference,
+{
+    struct EdgeMapper<F, L> {
+        index: usize,
+        last_index: usize,
+        first: Option<F>,
+        last: Option<L>,
+    }
+    impl<F, L> VisitorMut for EdgeMapper<F, L>
+    where
+        F: FnOnce(TokenReference) -> TokenReference,
+        L: FnOnce(TokenReference) -> TokenReference,
+    {
+        fn visit_token_reference(&mut self, node: TokenReference) -> TokenReference {
+            let index = self.index;
+            self.index += 1;
+            let mut node = node;
+            if index == 0 {
+                if let Some(f) = self.first.take() {
+                    node = f(node);
+                }
+            }
+            if index == self.last_index {
+                if let Some(l) = self.last.take() {
+                    node = l(node);
+                }
+            }
+            node
+        }
+    }
+
+    let last_index = node.tokens().count().saturating_sub(1);
+    let mut v = EdgeMapper {
+        index: 0,
+        last_index,
+        first: Some(first),
+        last: Some(last),
+    };
+    node.visit_mut(&mut v)
+}
+
+/// Detach the leading trivia of the first token and/or the trailing trivia of the last token.
+pub(crate) fn split_trivia<N: Node + VisitMut>(
+    node: N,
+    take_leading: bool,
+    take_trailing: bool,
+) -> (Vec<Token>, N, Vec<Token>) {
+    let mut leading = Vec::new();
+    let mut trailing = Vec::new();
+    let node = map_edge_tokens(
+        node,
+        |tok| {
+            if !take_leading {
+                return tok;
+            }
+            leading = tok.leading_trivia().cloned().collect();
+            let kept = tok.trailing_trivia().cloned().collect();
+            TokenReference::new(vec![], tok.token().clone(), kept)
+        },
+        |tok| {
+            if !take_trailing {
+                return tok;
+            }
+            trailing = tok.trailing_trivia().cloned().collect();
+            let kept = tok.leading_trivia().cloned().collect();
+            TokenReference::new(kept, tok.token().clone(), vec![])
+        },
+    );
+    (leading, node, trailing)
+}
+
+/// Append `extra` to the trailing trivia of the last token of `node`.
+pub(crate) fn append_trailing<N: Node + VisitMut>(node: N, extra: Vec<Token>) -> N {
+    if extra.is_empty() {
+        return node;
+    }
+    map_edge_tokens(node, |tok| tok, |tok| append_token_trailing(&tok, extra))
+}
+
+/// Prepend `extra` to the leading trivia of the first token of `node`.
+pub(crate) fn prepend_leading<N: Node + VisitMut>(node: N, extra: Vec<Token>) -> N {
+    if extra.is_empty() {
+        return node;
+    }
+    map_edge_tokens(
+        node,
+        |tok| {
+            let mut leading = extra;
+            leading.extend(tok.leading_trivia().cloned());
+            let trailing = tok.trailing_trivia().cloned().collect();
+            TokenReference::new(leading, tok.token().clone(), trailing)
+        },
+        |tok| tok,
+    )
+}
+
+pub(crate) fn append_token_trailing(tok: &TokenReference, extra: Vec<Token>) -> TokenReference {
+    let leading = tok.leading_trivia().cloned().collect();
+    let mut trailing: Vec<Token> = tok.trailing_trivia().cloned().collect();
+    trailing.extend(extra);
+    TokenReference::new(leading, tok.token().clone(), trailing)
+}
+
+/// Trivia to keep in place of a removed node so following lines keep their numbers:
+/// one whitespace token holding the node's line breaks.
+pub(crate) fn line_breaks_of(node: &impl Node) -> Vec<Token> {
+    let breaks = node
+        .tokens()
+        .map(|t| t.to_string().matches('\n').count())
+        .sum::<usize>();
+    if breaks == 0 {
+        return Vec::new();
+    }
+    vec![whitespace(&"\n".repeat(breaks))]
+}
+
+/// Keep trivia only when dropping it would lose a comment or a line break.
+pub(crate) fn significant_trivia(trivia: Vec<Token>) -> Vec<Token> {
+    let keep = trivia.iter().any(|t| {
+        !matches!(t.token_type(), TokenType::Whitespace { characters } if !characters.contains('\n'))
+    });
+    if keep {
+        trivia
+    } else {
+        Vec::new()
+    }
+}
+
+pub(crate) fn whitespace(characters: &str) -> Token {
+    Token::new(TokenType::Whitespace {
+        characters: characters.into(),
+    })
+}
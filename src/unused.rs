@@ -0,0 +1,31 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::include::{canonical, template_modules};
+use crate::options::NameNormalizer;
+
+/// A first-party file that no entry requires, directly or indirectly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnusedModule {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Every file matched by the `--path` templates that is not in `used`, sorted by module name.
+///
+/// `used` holds the entries and every file reachable from them; paths are compared after
+/// canonicalization, so relative and absolute spellings of the same file match.
+pub fn find_unused_modules(
+    templates: &[String],
+    normalizer: &NameNormalizer,
+    used: &[PathBuf],
+) -> Vec<UnusedModule> {
+    let used: HashSet<PathBuf> = used.iter().map(|p| canonical(p)).collect();
+    let mut out: Vec<UnusedModule> = template_modules(templates, normalizer)
+        .into_iter()
+        .filter(|(_, path)| !used.contains(&canonical(path)))
+        .map(|(name, path)| UnusedModule { name, path })
+        .collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path)));
+    out
+}
@@ -0,0 +1,251 @@
+#![cfg_attr(
+    not(any(feature = "lua53", feature = "luau")),
+    expect(
+        dead_code,
+        reason = "without the lua53 or luau features only number literals and goto can be checked"
+    )
+)]
+
+#[cfg(feature = "lua53")]
+use full_moon::ast::BinOp;
+use full_moon::ast::{self, Ast, Expression, UnOp};
+use full_moon::node::Node;
+use full_moon::tokenizer::{Position, Token, TokenReference};
+use full_moon::visitors::Visitor;
+
+use crate::dialect::{parse_lua, DialectIssue, LuaDialect};
+
+/// Check `code` against the syntax accepted by `dialect`.
+///
+/// The code is parsed with every enabled full_moon version to point at the exact
+/// constructs the target lacks (integer division, bitwise operators, `goto`,
+/// attributes, LuaJIT-only number literals, Luau types and syntax). Anything else the dialect's own
+/// parser rejects is reported as a plain syntax error.
+pub fn validate_dialect(code: &str, dialect: LuaDialect) -> Vec<DialectIssue> {
+    match parse_lua(code) {
+        Ok(ast) => validate_ast(&ast, code, dialect),
+        Err(errors) => errors
+            .iter()
+            .map(|e| issue_at(e.range().0, e.error_message().into_owned()))
+            .collect(),
+    }
+}
+
+/// [`validate_dialect`] for a chunk already parsed from `code` (possibly rewritten since).
+/// Only the final check against the target grammar parses `code` again.
+pub(crate) fn validate_ast(ast: &Ast, code: &str, dialect: LuaDialect) -> Vec<DialectIssue> {
+    let mut v = DialectChecker {
+        dialect,
+        issues: Vec::new(),
+        #[cfg(feature = "luau")]
+        type_depth: 0,
+    };
+    v.visit_ast(ast);
+    if !v.issues.is_empty() {
+        v.issues.sort_by_key(|i| (i.line, i.col));
+        return v.issues;
+    }
+
+    if let Err(errors) = full_moon::parse_fallible(code, dialect.lua_version()).into_result() {
+        return errors
+            .iter()
+            .map(|e| {
+                issue_at(
+                    e.range().0,
+                    format!("not valid Lua {}: {}", dialect, e.error_message()),
+                )
+            })
+            .collect();
+    }
+    Vec::new()
+}
+
+fn issue_at(pos: Position, message: String) -> DialectIssue {
+    DialectIssue {
+        line: pos.line(),
+        col: pos.character(),
+        message,
+    }
+}
+
+struct DialectChecker {
+    dialect: LuaDialect,
+    issues: Vec<DialectIssue>,
+    /// Nesting inside a reported type declaration; its contents are not reported again.
+    #[cfg(feature = "luau")]
+    type_depth: usize,
+}
+
+impl DialectChecker {
+    fn report(&mut self, token: &TokenReference, what: &str) {
+        self.issues.push(issue_at(
+            token.token().start_position(),
+            format!("{} is not supported by Lua {}", what, self.dialect),
+        ));
+    }
+
+    /// Report a Luau-only construct unless targeting Luau or inside a reported declaration.
+    #[cfg(feature = "luau")]
+    fn report_luau(&mut self, node: &impl Node, what: &str) {
+        if self.dialect != LuaDialect::Luau && self.type_depth == 0 {
+            self.report_node(node, what);
+        }
+    }
+
+    fn report_node(&mut self, node: &impl Node, what: &str) {
+        let pos = node.start_position().unwrap_or_default();
+        self.issues.push(issue_at(
+            pos,
+            format!("{} is not supported by Lua {}", what, self.dialect),
+        ));
+    }
+}
+
+impl Visitor for DialectChecker {
+    fn visit_expression(&mut self, node: &Expression) {
+        #[cfg(feature = "lua53")]
+        {
+            if let Expression::BinaryOperator { binop, .. } = node {
+                match binop {
+                    BinOp::DoubleSlash(tok) if !self.dialect.has_integer_division() => {
+                        self.report(tok, "integer division `//`")
+                    }
+                    BinOp::Ampersand(tok)
+                    | BinOp::Pipe(tok)
+                    | BinOp::Tilde(tok)
+                    | BinOp::DoubleLessThan(tok)
+                    | BinOp::DoubleGreaterThan(tok)
+                        if !self.dialect.has_bitwise_operators() =>
+                    {
+                        let what = format!("bitwise operator `{}`", tok.token());
+                        self.report(tok, &what)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        #[cfg(not(feature = "lua53"))]
+        let _ = node;
+    }
+
+    fn visit_un_op(&mut self, node: &UnOp) {
+        #[cfg(feature = "lua53")]
+        {
+            if let UnOp::Tilde(tok) = node {
+                if !self.dialect.has_bitwise_operators() {
+                    self.report(tok, "bitwise operator `~`");
+                }
+            }
+        }
+        #[cfg(not(feature = "lua53"))]
+        let _ = node;
+    }
+
+    fn visit_local_assignment(&mut self, node: &ast::LocalAssignment) {
+        #[cfg(feature = "lua54")]
+        {
+            if !self.dialect.has_attributes() {
+                for attr in node.attributes().flatten() {
+                    let what = format!("attribute `<{}>`", attr.name().token());
+                    self.report_node(attr, &what);
+                }
+            }
+        }
+        #[cfg(not(feature = "lua54"))]
+        let _ = node;
+    }
+
+    #[cfg(any(feature = "lua52", feature = "luajit"))]
+    fn visit_goto(&mut self, node: &ast::lua52::Goto) {
+        if !self.dialect.has_goto() {
+            self.report_node(node, "`goto`");
+        }
+    }
+
+    #[cfg(any(feature = "lua52", feature = "luajit"))]
+    fn visit_label(&mut self, node: &ast::lua52::Label) {
+        if !self.dialect.has_goto() {
+            self.report_node(node, "label");
+        }
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_specifier(&mut self, node: &ast::luau::TypeSpecifier) {
+        self.report_luau(node, "type annotation");
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_assertion(&mut self, node: &ast::luau::TypeAssertion) {
+        self.report_luau(node, "type cast `::`");
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_generic_declaration(&mut self, node: &ast::luau::GenericDeclaration) {
+        self.report_luau(node, "generic type parameters");
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_declaration(&mut self, node: &ast::luau::TypeDeclaration) {
+        self.report_luau(node, "type declaration");
+        self.type_depth += 1;
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_declaration_end(&mut self, _: &ast::luau::TypeDeclaration) {
+        self.type_depth -= 1;
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_function(&mut self, node: &ast::luau::TypeFunction) {
+        self.report_luau(node, "type function");
+        self.type_depth += 1;
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_function_end(&mut self, _: &ast::luau::TypeFunction) {
+        self.type_depth -= 1;
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_compound_assignment(&mut self, node: &ast::CompoundAssignment) {
+        let what = format!("compound assignment `{}`", node.compound_operator());
+        self.report_luau(node, &what);
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_if_expression(&mut self, node: &ast::luau::IfExpression) {
+        self.report_luau(node, "if-expression");
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_interpolated_string(&mut self, node: &ast::luau::InterpolatedString) {
+        self.report_luau(node, "string interpolation");
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_last_stmt(&mut self, node: &ast::LastStmt) {
+        if let ast::LastStmt::Continue(tok) = node {
+            if self.dialect != LuaDialect::Luau {
+                self.report(tok, "`continue`");
+            }
+        }
+    }
+
+    fn visit_number(&mut self, token: &Token) {
+        if self.dialect == LuaDialect::LuaJit {
+            return;
+        }
+        let text = token.to_string().to_ascii_lowercase();
+        let jit_suffix = text.ends_with("ll") || text.ends_with('i');
+        let binary = text.starts_with("0b") && self.dialect != LuaDialect::Luau;
+        if jit_suffix || binary {
+            self.issues.push(issue_at(
+                token.start_position(),
+                format!(
+                    "LuaJIT number literal `{}` is not supported by Lua {}",
+                    token, self.dialect
+                ),
+            ));
+        }
+    }
+}
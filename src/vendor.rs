@@ -1,9 +1,14 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use glob::glob;
+use clap::ValueEnum;
+use glob::{glob, Pattern};
+use regex::Regex;
 
+use crate::graph::ModuleGraph;
 use crate::normalize::normalize_module_name;
 use crate::options::NameNormalizer;
 use crate::replace::{matches_replace, MatchKind, ReplaceRule};
@@ -11,12 +16,52 @@ use crate::replace::{matches_replace, MatchKind, ReplaceRule};
 #[derive(Debug, Clone)]
 pub struct VendorSpec {
     pub paths: Vec<String>,
+    /// `include=name:` and `include=glob:`; when any is given, only matching modules are kept.
+    pub include_names: Vec<String>,
+    pub include_globs: Vec<Pattern>,
     pub exclude_names: Vec<String>,
     pub exclude_prefixes: Vec<String>,
+    pub exclude_globs: Vec<Pattern>,
+    pub exclude_regexes: Vec<Regex>,
     pub suffixes: Vec<String>,
 }
 
-fn path_rule_matches(replaces: &[ReplaceRule], path: &Path) -> bool {
+impl VendorSpec {
+    /// Why the filters of this spec leave out module `name` found at `path`, if they do.
+    ///
+    /// Globs and regexes are tried against the normalized module name and against the file
+    /// path; exclusions win over the include allowlist.
+    pub fn rejects(&self, name: &str, path: &Path) -> Option<String> {
+        let file = path.to_string_lossy().replace('\\', "/");
+        let glob_hits = |p: &Pattern| p.matches(name) || p.matches(&file);
+        if self.exclude_names.iter().any(|n| n == name) {
+            return Some(format!("excluded by exclude=name:{}", name));
+        }
+        if let Some(p) = self.exclude_prefixes.iter().find(|p| name.starts_with(*p)) {
+            return Some(format!("excluded by exclude=prefix:{}", p));
+        }
+        if let Some(p) = self.exclude_globs.iter().find(|p| glob_hits(p)) {
+            return Some(format!("excluded by exclude=glob:{}", p));
+        }
+        if let Some(r) = self
+            .exclude_regexes
+            .iter()
+            .find(|r| r.is_match(name) || r.is_match(&file))
+        {
+            return Some(format!("excluded by exclude=regex:{}", r));
+        }
+        let allowlist = !self.include_names.is_empty() || !self.include_globs.is_empty();
+        if allowlist
+            && !self.include_names.iter().any(|n| n == name)
+            && !self.include_globs.iter().any(glob_hits)
+        {
+            return Some("not matched by any include=".to_string());
+        }
+        None
+    }
+}
+
+pub(crate) fn path_rule_matches(replaces: &[ReplaceRule], path: &Path) -> bool {
     let p = path.to_string_lossy().replace('\\', "/");
     for r in replaces {
         if r.match_kind == MatchKind::Path {
@@ -35,10 +80,16 @@ fn path_rule_matches(replaces: &[ReplaceRule], path: &Path) -> bool {
 pub fn parse_vendor_specs(flags: &[String]) -> Result<Vec<VendorSpec>> {
     let mut out = Vec::new();
     for raw in flags {
-        let mut paths: Vec<String> = Vec::new();
-        let mut exclude_names: Vec<String> = Vec::new();
-        let mut exclude_prefixes: Vec<String> = Vec::new();
-        let mut suffixes: Vec<String> = Vec::new();
+        let mut spec = VendorSpec {
+            paths: Vec::new(),
+            include_names: Vec::new(),
+            include_globs: Vec::new(),
+            exclude_names: Vec::new(),
+            exclude_prefixes: Vec::new(),
+            exclude_globs: Vec::new(),
+            exclude_regexes: Vec::new(),
+            suffixes: Vec::new(),
+        };
         for part in raw.split(',') {
             let s = part.trim();
             if s.is_empty() {
@@ -46,41 +97,224 @@ pub fn parse_vendor_specs(flags: &[String]) -> Result<Vec<VendorSpec>> {
             }
             if let Some((k, v)) = s.split_once('=') {
                 match k.trim() {
-                    "path" => paths.push(v.trim().to_string()),
+                    "path" => spec.paths.push(v.trim().to_string()),
+                    "include" => {
+                        if let Some((kind, val)) = v.split_once(':') {
+                            let val = val.trim();
+                            match kind.trim() {
+                                "name" => spec.include_names.push(val.to_string()),
+                                "glob" => spec.include_globs.push(
+                                    Pattern::new(val)
+                                        .with_context(|| format!("bad include=glob:{}", val))?,
+                                ),
+                                _ => {}
+                            }
+                        }
+                    }
                     "exclude" => {
                         if let Some((kind, val)) = v.split_once(':') {
+                            let val = val.trim();
                             match kind.trim() {
-                                "name" => exclude_names.push(val.trim().to_string()),
-                                "prefix" => exclude_prefixes.push(val.trim().to_string()),
+                                "name" => spec.exclude_names.push(val.to_string()),
+                                "prefix" => spec.exclude_prefixes.push(val.to_string()),
+                                "glob" => spec.exclude_globs.push(
+                                    Pattern::new(val)
+                                        .with_context(|| format!("bad exclude=glob:{}", val))?,
+                                ),
+                                "regex" => spec.exclude_regexes.push(
+                                    Regex::new(val)
+                                        .with_context(|| format!("bad exclude=regex:{}", val))?,
+                                ),
                                 _ => {}
                             }
                         }
                     }
-                    "suffix" => suffixes.push(v.trim().to_string()),
+                    "suffix" => spec.suffixes.push(v.trim().to_string()),
                     _ => {}
                 }
             } else {
                 // Bare value: treat as a path template directly
-                paths.push(s.to_string());
+                spec.paths.push(s.to_string());
             }
         }
-        out.push(VendorSpec {
-            paths,
-            exclude_names,
-            exclude_prefixes,
-            suffixes,
-        });
+        out.push(spec);
     }
     Ok(out)
 }
 
+/// Which file is bundled when two vendor files claim the same module name with different
+/// content (`--vendor-conflict`).
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum VendorConflictPolicy {
+    /// Keep the file found first, in `--vendor` order.
+    #[default]
+    First,
+    /// Keep the file found last.
+    Last,
+    /// Fail the build.
+    Error,
+}
+
+/// Two vendor files with the same module name and different content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendorConflict {
+    pub name: String,
+    /// The file that is bundled.
+    pub kept: PathBuf,
+    /// The file that is left out.
+    pub dropped: PathBuf,
+    /// How the two differ, e.g. `120 vs 134 lines; first difference at line 5: ...`.
+    pub summary: String,
+}
+
+impl fmt::Display for VendorConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "vendor module '{}' differs between {} and {} ({})",
+            self.name,
+            self.kept.display(),
+            self.dropped.display(),
+            self.summary
+        )
+    }
+}
+
+/// What is bundled when a first-party module has the same name as a vendor module
+/// (`--vendor-shadow`).
+#[derive(Copy, Clone, Debug, Default, ValueEnum, PartialEq, Eq)]
+pub enum VendorShadowPolicy {
+    /// Bundle the first-party file and warn.
+    #[default]
+    FirstParty,
+    /// Bundle the vendor file instead.
+    Vendor,
+    /// Fail the build.
+    Error,
+}
+
+/// A first-party module, or an alias of one, named like a vendor module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendorShadow {
+    pub name: String,
+    pub first_party: PathBuf,
+    pub vendor: PathBuf,
+    /// Other names the first-party module is required by, sorted. With
+    /// [`VendorShadowPolicy::Vendor`] they lead to the vendor file as well.
+    pub aliases: Vec<String>,
+}
+
+impl fmt::Display for VendorShadow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "first-party module '{}' ({}) shadows vendor module ({})",
+            self.name,
+            self.first_party.display(),
+            self.vendor.display()
+        )?;
+        if !self.aliases.is_empty() {
+            write!(f, ", also required as {}", self.aliases.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Vendor modules whose name is already taken by the first-party graph, sorted by name.
+pub fn find_vendor_shadows(
+    graph: &ModuleGraph,
+    vendor_mods: &HashMap<String, PathBuf>,
+) -> Vec<VendorShadow> {
+    let mut shadows: Vec<VendorShadow> = vendor_mods
+        .iter()
+        .filter_map(|(name, vendor)| {
+            let first_party = graph.first_party.get(name).or_else(|| {
+                let target = graph.aliases.get(name)?;
+                graph.first_party.get(target)
+            })?;
+            let mut aliases: Vec<String> = graph
+                .aliases
+                .iter()
+                .filter(|(_, target)| *target == name)
+                .map(|(alias, _)| alias.clone())
+                .collect();
+            aliases.sort();
+            Some(VendorShadow {
+                name: name.clone(),
+                first_party: first_party.clone(),
+                vendor: vendor.clone(),
+                aliases,
+            })
+        })
+        .collect();
+    shadows.sort_by(|a, b| a.name.cmp(&b.name));
+    shadows
+}
+
+/// Settles the shadowed names of [`find_vendor_shadows`] by `policy` and returns them.
+///
+/// [`VendorShadowPolicy::Vendor`] drops the shadowing first-party modules from `graph`, with
+/// whatever only they required, so the vendor files are bundled under those names; with
+/// [`VendorShadowPolicy::Error`] any shadowing fails with all of them listed.
+pub fn apply_vendor_shadows(
+    graph: &mut ModuleGraph,
+    vendor_mods: &HashMap<String, PathBuf>,
+    policy: VendorShadowPolicy,
+) -> Result<Vec<VendorShadow>> {
+    let shadows = find_vendor_shadows(graph, vendor_mods);
+    match policy {
+        VendorShadowPolicy::FirstParty => {}
+        VendorShadowPolicy::Error if !shadows.is_empty() => {
+            let listing: Vec<String> = shadows.iter().map(|s| format!("  {}", s)).collect();
+            anyhow::bail!(
+                "{} first-party module(s) shadow vendor modules:\n{}",
+                shadows.len(),
+                listing.join("\n")
+            );
+        }
+        VendorShadowPolicy::Error => {}
+        VendorShadowPolicy::Vendor => {
+            graph.drop_modules(&shadows.iter().map(|s| s.name.clone()).collect());
+        }
+    }
+    Ok(shadows)
+}
+
+/// Vendor modules by name, with what was found more than once.
+#[derive(Debug, Clone, Default)]
+pub struct VendorModules {
+    pub modules: HashMap<String, PathBuf>,
+    /// Names found under more than one vendor root with identical content.
+    pub duplicates: HashSet<String>,
+    /// Names found with differing content; `kept` is the file in `modules`.
+    pub conflicts: Vec<VendorConflict>,
+}
+
 pub fn collect_vendor_modules(
     specs: &[VendorSpec],
     replaces: &[crate::replace::ReplaceRule],
     normalizer: &NameNormalizer,
 ) -> Result<(HashMap<String, PathBuf>, HashSet<String>)> {
-    let mut out: HashMap<String, PathBuf> = HashMap::new();
-    let mut dups: HashSet<String> = HashSet::new();
+    let found = collect_vendor_set(specs, replaces, normalizer, VendorConflictPolicy::First)?;
+    let mut dups = found.duplicates;
+    dups.extend(found.conflicts.into_iter().map(|c| c.name));
+    Ok((found.modules, dups))
+}
+
+/// Like [`collect_vendor_modules`], comparing the content of files that share a name.
+///
+/// `policy` picks the file bundled for each name; every other copy is compared with it and
+/// recorded as a duplicate when identical, or as a conflict. With
+/// [`VendorConflictPolicy::Error`] any conflict fails with all of them listed.
+pub fn collect_vendor_set(
+    specs: &[VendorSpec],
+    replaces: &[crate::replace::ReplaceRule],
+    normalizer: &NameNormalizer,
+    policy: VendorConflictPolicy,
+) -> Result<VendorModules> {
+    // Every distinct file found for a name, in `--vendor` order; names in discovery order.
+    let mut found: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut names: Vec<String> = Vec::new();
     for spec in specs {
         for t in &spec.paths {
             let (glob_pat, root_prefix, init_mode) = to_glob_and_root(t);
@@ -91,24 +325,11 @@ pub fn collect_vendor_modules(
                 if !path.is_file() {
                     continue;
                 }
-                let rel = match path.strip_prefix(&root_prefix) {
-                    Ok(r) => r,
-                    Err(_) => continue,
-                };
-                let rel_str = rel.to_string_lossy();
-                let raw_name = if init_mode {
-                    rel_str
-                        .trim_end_matches("/init.lua")
-                        .trim_end_matches("\\init.lua")
-                        .replace(['\\', '/'], ".")
-                } else {
-                    rel_str.trim_end_matches(".lua").replace(['\\', '/'], ".")
+                let Some(raw_name) = vendor_raw_name(&path, &root_prefix, init_mode) else {
+                    continue;
                 };
                 let name = normalize_module_name(&raw_name, normalizer);
-                if spec.exclude_names.iter().any(|n| n == &name) {
-                    continue;
-                }
-                if spec.exclude_prefixes.iter().any(|p| name.starts_with(p)) {
+                if spec.rejects(&name, &path).is_some() {
                     continue;
                 }
                 if matches_replace(&name, replaces, normalizer)
@@ -116,14 +337,114 @@ pub fn collect_vendor_modules(
                 {
                     continue;
                 }
-                if out.contains_key(&name) {
-                    dups.insert(name.clone());
+                let Some(files) = found.get_mut(&name) else {
+                    names.push(name.clone());
+                    found.insert(name, vec![path]);
+                    continue;
+                };
+                let key = fs::canonicalize(&path).ok();
+                if !files.iter().any(|f| fs::canonicalize(f).ok() == key) {
+                    files.push(path);
                 }
-                out.entry(name).or_insert(path);
             }
         }
     }
-    Ok((out, dups))
+
+    // Other files are compared with the one bundled, once the policy has picked it.
+    let mut out = VendorModules::default();
+    for name in names {
+        let mut files = found.remove(&name).unwrap_or_default();
+        let kept = match policy {
+            VendorConflictPolicy::Last => files.pop(),
+            _ => Some(files.remove(0)),
+        };
+        let Some(kept) = kept else {
+            continue;
+        };
+        if !files.is_empty() {
+            let kept_text = read_vendor(&kept)?;
+            for dropped in files {
+                let text = read_vendor(&dropped)?;
+                if text == kept_text {
+                    out.duplicates.insert(name.clone());
+                    continue;
+                }
+                out.conflicts.push(VendorConflict {
+                    name: name.clone(),
+                    kept: kept.clone(),
+                    dropped,
+                    summary: diff_summary(&kept_text, &text),
+                });
+            }
+        }
+        out.modules.insert(name, kept);
+    }
+    if policy == VendorConflictPolicy::Error && !out.conflicts.is_empty() {
+        let listing: Vec<String> = out.conflicts.iter().map(|c| format!("  {}", c)).collect();
+        anyhow::bail!(
+            "{} vendor module conflict(s):\n{}",
+            out.conflicts.len(),
+            listing.join("\n")
+        );
+    }
+    Ok(out)
+}
+
+fn read_vendor(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .with_context(|| format!("failed to read vendor module: {}", path.display()))
+}
+
+/// One-line description of how `b` differs from `a`: line counts, the first differing line
+/// and how many lines only one side has.
+pub(crate) fn diff_summary(a: &str, b: &str) -> String {
+    let (la, lb): (Vec<&str>, Vec<&str>) = (a.lines().collect(), b.lines().collect());
+    let mut summary = format!("{} vs {} lines", la.len(), lb.len());
+    let first = (0..la.len().max(lb.len())).find(|&i| la.get(i) != lb.get(i));
+    if let Some(i) = first {
+        let show = |line: Option<&&str>| match line {
+            Some(l) => format!("`{}`", clip(l.trim(), 40)),
+            None => "end of file".to_string(),
+        };
+        summary.push_str(&format!(
+            "; first difference at line {}: {} vs {}",
+            i + 1,
+            show(la.get(i)),
+            show(lb.get(i))
+        ));
+    }
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for l in &la {
+        *counts.entry(l).or_default() += 1;
+    }
+    for l in &lb {
+        *counts.entry(l).or_default() -= 1;
+    }
+    let only_a: isize = counts.values().filter(|&&n| n > 0).sum();
+    let only_b: isize = -counts.values().filter(|&&n| n < 0).sum::<isize>();
+    summary.push_str(&format!("; -{} +{} lines", only_a, only_b));
+    summary
+}
+
+fn clip(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((i, _)) => format!("{}...", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+/// Module name of a vendor file relative to its template root, before normalization.
+pub(crate) fn vendor_raw_name(path: &Path, root_prefix: &Path, init_mode: bool) -> Option<String> {
+    let rel = path.strip_prefix(root_prefix).ok()?;
+    let rel_str = rel.to_string_lossy();
+    Some(if init_mode {
+        rel_str
+            .trim_end_matches("/init.lua")
+            .trim_end_matches("\\init.lua")
+            .replace(['\\', '/'], ".")
+    } else {
+        rel_str.trim_end_matches(".lua").replace(['\\', '/'], ".")
+    })
 }
 
 pub fn to_glob_and_root(t: &str) -> (String, PathBuf, bool) {
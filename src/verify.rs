@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+
+use full_moon::ast::{self, Expression, FunctionArgs, FunctionCall, Index, Prefix, Suffix, Var};
+use full_moon::node::Node;
+use full_moon::tokenizer::{TokenReference, TokenType};
+use full_moon::visitors::Visitor;
+use glob::Pattern;
+
+use crate::dialect::{parse_lua, DialectIssue, LuaDialect};
+use crate::options::NameNormalizer;
+use crate::replace::ReplaceRule;
+use crate::resolve::ModuleResolver;
+use crate::scan::{ignored_lines, is_ignored};
+use crate::transform::find_replacement;
+use crate::validate::validate_dialect;
+
+/// What [`verify_bundle`] needs to know about the build that produced the bundle.
+pub struct VerifyCtx<'a> {
+    pub dialect: LuaDialect,
+    pub replaces: &'a [ReplaceRule],
+    /// Module-name globs of modules provided by the host.
+    pub externals: &'a [String],
+    /// Resolver of the build; requires of excluded first-party files count as external.
+    pub resolver: Option<&'a ModuleResolver>,
+    pub normalizer: &'a NameNormalizer,
+}
+
+/// Modules found in a bundle and everything wrong with it.
+#[derive(Debug, Clone, Default)]
+pub struct BundleReport {
+    /// `__B_MODULES[...]` registrations in bundle order.
+    pub modules: Vec<String>,
+    /// `__B_ALIASES[alias] = name` entries.
+    pub aliases: Vec<(String, String)>,
+    pub issues: Vec<DialectIssue>,
+}
+
+/// Statically check a generated bundle.
+///
+/// The bundle must be valid for the target dialect, and every literal `require` inside a
+/// registered module, every alias and every `__B_REQUIRE('...')` must reach a registered
+/// module, a replace rule or an external module.
+pub fn verify_bundle(code: &str, ctx: &VerifyCtx) -> BundleReport {
+    let mut report = BundleReport {
+        issues: validate_dialect(code, ctx.dialect),
+        ..Default::default()
+    };
+    let Ok(ast) = parse_lua(code) else {
+        return report;
+    };
+
+    let mut scan = BundleScan {
+        ignored: ignored_lines(&ast),
+        ..Default::default()
+    };
+    scan.visit_ast(&ast);
+    if scan.modules.is_empty() {
+        report.issues.push(issue(
+            (1, 1),
+            "no __B_MODULES registrations found; not a luapack bundle?".to_string(),
+        ));
+    }
+
+    let externals: Vec<Pattern> = ctx
+        .externals
+        .iter()
+        .filter_map(|g| Pattern::new(g).ok())
+        .collect();
+    let mut registered: HashSet<&str> = HashSet::new();
+    for (name, pos) in &scan.modules {
+        if !registered.insert(name) {
+            report.issues.push(issue(
+                *pos,
+                format!("module '{}' is registered twice", name),
+            ));
+        }
+    }
+    let aliased: HashSet<&str> = scan.aliases.iter().map(|(a, _, _)| a.as_str()).collect();
+    let known = |name: &str| registered.contains(name) || aliased.contains(name);
+
+    for (alias, name, pos) in &scan.aliases {
+        if !registered.contains(name.as_str()) {
+            report.issues.push(issue(
+                *pos,
+                format!("alias '{}' points to unregistered module '{}'", alias, name),
+            ));
+        }
+    }
+    for (name, first, pos) in &scan.shared {
+        if !registered.contains(first.as_str()) {
+            report.issues.push(issue(
+                *pos,
+                format!(
+                    "module '{}' shares the loader of unregistered module '{}'",
+                    name, first
+                ),
+            ));
+        }
+    }
+    for (name, pos) in &scan.entries {
+        if !known(name) {
+            report.issues.push(issue(
+                *pos,
+                format!("__B_REQUIRE('{}') names an unregistered module", name),
+            ));
+        }
+    }
+    for req in &scan.requires {
+        let routed = find_replacement(&req.name, ctx.replaces, None, None, ctx.normalizer);
+        let excluded = ctx
+            .resolver
+            .is_some_and(|r| r.excluded(&req.name).is_some());
+        if known(&req.name)
+            || routed.is_some()
+            || excluded
+            || externals.iter().any(|p| p.matches(&req.name))
+        {
+            continue;
+        }
+        report.issues.push(issue(
+            req.pos,
+            format!(
+                "require(\"{}\") in module '{}' is not bundled, replaced or external",
+                req.name, req.module
+            ),
+        ));
+    }
+
+    report.issues.sort_by_key(|i| (i.line, i.col));
+    report.modules = scan.modules.into_iter().map(|(n, _)| n).collect();
+    report.aliases = scan.aliases.into_iter().map(|(a, n, _)| (a, n)).collect();
+    report
+}
+
+type Pos = (usize, usize);
+
+fn issue(pos: Pos, message: String) -> DialectIssue {
+    DialectIssue {
+        line: pos.0,
+        col: pos.1,
+        message,
+    }
+}
+
+struct ModuleRequire {
+    module: String,
+    name: String,
+    pos: Pos,
+}
+
+#[derive(Default)]
+struct BundleScan {
+    modules: Vec<(String, Pos)>,
+    aliases: Vec<(String, String, Pos)>,
+    /// `__B_MODULES[name] = __B_MODULES[first]`: identical vendor modules sharing a loader.
+    shared: Vec<(String, String, Pos)>,
+    entries: Vec<(String, Pos)>,
+    requires: Vec<ModuleRequire>,
+    /// Registrations whose loader is being visited, innermost last.
+    current: Vec<Option<String>>,
+    /// Lines marked `-- luapack: ignore`, whose requires are not checked.
+    ignored: HashSet<usize>,
+}
+
+impl Visitor for BundleScan {
+    fn visit_assignment(&mut self, node: &ast::Assignment) {
+        let mut module = None;
+        for (var, expr) in node.variables().iter().zip(node.expressions().iter()) {
+            let pos = start_of(var);
+            match (table_key(var), expr) {
+                (Some(("__B_MODULES", name)), Expression::Function(_)) => {
+                    self.modules.push((name.clone(), pos));
+                    module = Some(name);
+                }
+                (Some(("__B_MODULES", name)), Expression::Var(source)) => {
+                    if let Some(("__B_MODULES", first)) = table_key(source) {
+                        self.modules.push((name.clone(), pos));
+                        self.shared.push((name, first, pos));
+                    }
+                }
+                (Some(("__B_ALIASES", alias)), Expression::String(tok)) => {
+                    if let Some(name) = string_literal(tok) {
+                        self.aliases.push((alias, name, pos));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.current.push(module);
+    }
+
+    fn visit_assignment_end(&mut self, _: &ast::Assignment) {
+        self.current.pop();
+    }
+
+    fn visit_function_call(&mut self, node: &FunctionCall) {
+        let Prefix::Name(callee) = node.prefix() else {
+            return;
+        };
+        let Some(name) = literal_argument(node) else {
+            return;
+        };
+        let pos = start_of(node);
+        match identifier(callee) {
+            Some("__B_REQUIRE") => self.entries.push((name, pos)),
+            Some("require") if !is_ignored(node, &self.ignored) => {
+                if let Some(module) = self.current.iter().rev().flatten().next() {
+                    self.requires.push(ModuleRequire {
+                        module: module.clone(),
+                        name,
+                        pos,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn start_of(node: &impl Node) -> Pos {
+    node.start_position()
+        .map(|p| (p.line(), p.character()))
+        .unwrap_or((0, 0))
+}
+
+fn identifier(tok: &TokenReference) -> Option<&str> {
+    match tok.token().token_type() {
+        TokenType::Identifier { identifier } => Some(identifier.as_str()),
+        _ => None,
+    }
+}
+
+fn string_literal(tok: &TokenReference) -> Option<String> {
+    match tok.token().token_type() {
+        TokenType::StringLiteral { literal, .. } => Some(literal.to_string()),
+        _ => None,
+    }
+}
+
+/// `table['key']` as `(table, key)`.
+fn table_key(var: &Var) -> Option<(&str, String)> {
+    let Var::Expression(expr) = var else {
+        return None;
+    };
+    let Prefix::Name(table) = expr.prefix() else {
+        return None;
+    };
+    let mut suffixes = expr.suffixes();
+    let Some(Suffix::Index(Index::Brackets {
+        expression: Expression::String(key),
+        ..
+    })) = suffixes.next()
+    else {
+        return None;
+    };
+    if suffixes.next().is_some() {
+        return None;
+    }
+    Some((identifier(table)?, string_literal(key)?))
+}
+
+/// The string of `f("x")` / `f "x"`.
+fn literal_argument(call: &FunctionCall) -> Option<String> {
+    let Some(Suffix::Call(ast::Call::AnonymousCall(args))) = call.suffixes().next() else {
+        return None;
+    };
+    match args {
+        FunctionArgs::Parentheses { arguments, .. } if arguments.len() == 1 => {
+            match arguments.iter().next() {
+                Some(Expression::String(tok)) => string_literal(tok),
+                _ => None,
+            }
+        }
+        FunctionArgs::String(tok) => string_literal(tok),
+        _ => None,
+    }
+}
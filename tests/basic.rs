@@ -57,9 +57,21 @@ fn bundle_simple_smoke_integration() {
         entry_source: &code,
         entry_path: &entry,
         bind: _BindRequireExport::Router,
+        runtime_trace: false,
+        introspect: false,
+        hot_reload: false,
+        sandbox: None,
+        instrument: None,
         resolver: Some(&resolver),
         redact_base: None,
         normalizer: &normalizer,
+        dialect: LuaDialect::default(),
+        strip_types: false,
+        strip_asserts: false,
+        strip_marked: false,
+        downlevel: false,
+        validate: true,
+        cache: None,
     };
     let (bundle, _rewrites) = generate_bundle(&graph, ctx).expect("bundle");
     assert!(
@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use luapack::*;
 
@@ -25,6 +27,42 @@ pub fn build_graph(
     (entry, code, resolver, graph)
 }
 
+/// A `BundleCtx` with the settings tests share: router binding, default dialect, validation,
+/// paths redacted against the crate root and nothing else enabled. Tests override fields
+/// with `BundleCtx { hot_reload: true, ..bundle_ctx(...) }`.
+pub fn bundle_ctx<'a>(
+    entry_path: &'a Path,
+    entry_source: &'a str,
+    resolver: &'a ModuleResolver,
+    normalizer: &'a NameNormalizer,
+) -> BundleCtx<'a> {
+    static NO_VENDOR: OnceLock<HashMap<String, PathBuf>> = OnceLock::new();
+    BundleCtx {
+        preludes: &[],
+        entry: None,
+        replaces: &[],
+        vendor_mods: NO_VENDOR.get_or_init(HashMap::new),
+        entry_source,
+        entry_path,
+        bind: _BindRequireExport::Router,
+        runtime_trace: false,
+        introspect: false,
+        hot_reload: false,
+        sandbox: None,
+        instrument: None,
+        resolver: Some(resolver),
+        redact_base: Some(PathBuf::from(env!("CARGO_MANIFEST_DIR"))),
+        normalizer,
+        dialect: LuaDialect::default(),
+        strip_types: false,
+        strip_asserts: false,
+        strip_marked: false,
+        downlevel: false,
+        validate: true,
+        cache: None,
+    }
+}
+
 pub struct BundleOptions<'a> {
     pub entry_override: Option<&'a str>,
     pub preludes: &'a [PathBuf],
@@ -33,6 +71,15 @@ pub struct BundleOptions<'a> {
 }
 
 pub fn bundle_for(entry_path: &str, paths: &[&str], opts: BundleOptions<'_>) -> (String, usize) {
+    let (bundle, stats) = bundle_with_stats_for(entry_path, paths, opts);
+    (bundle, stats.rewrites)
+}
+
+pub fn bundle_with_stats_for(
+    entry_path: &str,
+    paths: &[&str],
+    opts: BundleOptions<'_>,
+) -> (String, BundleStats) {
     // Parse vendor specs and compute suffix normalizer
     let abs_vendor_specs: Vec<String> = opts
         .vendor_specs
@@ -65,8 +112,6 @@ pub fn bundle_for(entry_path: &str, paths: &[&str], opts: BundleOptions<'_>) ->
     let normalizer = NameNormalizer::new(suffixes);
 
     let (entry, code, resolver, graph) = build_graph(entry_path, paths, &normalizer);
-    // Stable path redaction for snapshots
-    let redact_base = Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
     let (vendor_mods, _dups) = collect_vendor_modules(&vendor_specs, opts.replaces, &normalizer)
         .expect("collect vendor modules");
     let ctx = BundleCtx {
@@ -74,12 +119,7 @@ pub fn bundle_for(entry_path: &str, paths: &[&str], opts: BundleOptions<'_>) ->
         entry: opts.entry_override,
         replaces: opts.replaces,
         vendor_mods: &vendor_mods,
-        entry_source: &code,
-        entry_path: &entry,
-        bind: _BindRequireExport::Router,
-        resolver: Some(&resolver),
-        redact_base,
-        normalizer: &normalizer,
+        ..bundle_ctx(&entry, &code, &resolver, &normalizer)
     };
-    generate_bundle(&graph, ctx).expect("bundle")
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
 }
@@ -0,0 +1,2 @@
+local util = require("util")
+return { hello = util.hello }
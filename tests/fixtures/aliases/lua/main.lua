@@ -0,0 +1,3 @@
+local greet = require("core.greet")
+local same = require("greet")
+return greet == same
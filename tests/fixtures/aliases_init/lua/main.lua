@@ -0,0 +1,4 @@
+local greet = require("core.greet")
+local same = require("core.greet.init")
+assert(greet == same, "one module under both names")
+return greet.hello
@@ -0,0 +1,19 @@
+local util = require("util")
+
+-- only positive numbers take the first branch
+assert(util.sign(3) == "+")
+if false then
+  print("never")
+end
+
+local cov = __LUAPACK_COVERAGE
+if cov then
+  local hits = cov.files["tests/fixtures/coverage/lua/util.lua"]
+  assert(hits[4] == 1 and hits[5] == 0, "line hits")
+  assert(cov.files["tests/fixtures/coverage/lua/main.lua"][6] == 0, "entry hits")
+  local report = cov.lcov()
+  assert(report:find("SF:tests/fixtures/coverage/lua/util.lua\nDA:1,1\n", 1, true), report)
+  assert(report:find("DA:5,0\n", 1, true), report)
+  assert(report:find("LF:8\nLH:4\nend_of_record\n", 1, true), report)
+end
+return util
@@ -0,0 +1,12 @@
+local M = {}
+
+function M.sign(n)
+  if n > 0 then return "+" end
+  local zero = n == 0
+  if zero then
+    return "0"
+  end
+  return "-"
+end
+
+return M
@@ -0,0 +1,7 @@
+local M = {}
+
+function M.start(tools, helper)
+  return tools ~= nil, helper ~= nil
+end
+
+return M
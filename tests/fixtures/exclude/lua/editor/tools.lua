@@ -0,0 +1 @@
+return { inspector = true }
@@ -0,0 +1,5 @@
+local app = require("app")
+local tools = require("editor.tools")
+local helper = require("test_helper") -- luapack: ignore
+
+return app.start(tools, helper)
@@ -0,0 +1 @@
+return { fake = true }
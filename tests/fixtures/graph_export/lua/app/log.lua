@@ -0,0 +1,5 @@
+return {
+	info = function(msg)
+		print(msg)
+	end,
+}
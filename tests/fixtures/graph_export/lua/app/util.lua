@@ -0,0 +1,8 @@
+local json = require("json")
+local log = require("app.log")
+
+return {
+	run = function(...)
+		log.info(json.encode({ ... }))
+	end,
+}
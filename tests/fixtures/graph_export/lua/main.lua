@@ -0,0 +1,6 @@
+local util = require("app.util")
+local net = require("svc.net")
+local gfx = require("love.graphics")
+local missing = require("missing.mod")
+
+return util.run(net, gfx, missing)
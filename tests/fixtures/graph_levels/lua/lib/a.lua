@@ -0,0 +1,2 @@
+local c = require('lib.c')
+return { run = function() return c.value end }
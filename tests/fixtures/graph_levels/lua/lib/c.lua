@@ -0,0 +1,2 @@
+local d = require 'lib.d'
+return { value = d }
@@ -0,0 +1,3 @@
+local a = require('lib.a')
+local b = require('lib.b')
+return a.run() + b.run()
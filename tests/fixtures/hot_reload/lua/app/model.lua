@@ -0,0 +1,2 @@
+MODEL_LOADS = (MODEL_LOADS or 0) + 1
+return { value = "v1" }
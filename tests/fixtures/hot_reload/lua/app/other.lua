@@ -0,0 +1,2 @@
+OTHER_LOADS = (OTHER_LOADS or 0) + 1
+return {}
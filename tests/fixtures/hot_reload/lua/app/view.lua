@@ -0,0 +1,3 @@
+local model = require("app.model")
+VIEW_TEXT = "view:" .. model.value
+return { text = VIEW_TEXT }
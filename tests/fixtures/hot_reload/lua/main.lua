@@ -0,0 +1,2 @@
+VIEW = require("app.view")
+require("app.other")
@@ -0,0 +1,13 @@
+local widgets = {}
+
+local function load_widget(name)
+  widgets[name] = widgets[name] or require("widgets." .. name)
+  return widgets[name]
+end
+
+local theme_name = "dark"
+local theme = require(("themes.%s"):format(theme_name))
+
+assert(load_widget("button").kind == "button")
+assert(load_widget("label").kind == "label")
+assert(theme.bg == "#000000")
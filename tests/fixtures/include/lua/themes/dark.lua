@@ -0,0 +1,2 @@
+local colors = require("util.colors")
+return { bg = colors.black }
@@ -0,0 +1 @@
+return { bg = "#ff00ff" }
@@ -0,0 +1 @@
+return { black = "#000000" }
@@ -0,0 +1 @@
+return { kind = "button" }
@@ -0,0 +1 @@
+return { kind = "label" }
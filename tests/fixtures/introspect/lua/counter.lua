@@ -0,0 +1,3 @@
+-- Counts how often the loader ran.
+LOADS = (LOADS or 0) + 1
+return { loads = LOADS }
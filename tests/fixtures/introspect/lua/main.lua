@@ -0,0 +1,28 @@
+local api = __LUAPACK
+local function check(cond, what)
+  if not cond then
+    error("check failed: " .. what, 2)
+  end
+end
+
+check(table.concat(api.modules(), ",") == "counter,lib.util", "modules")
+check(not api.loaded("counter"), "not loaded yet")
+local first = require("counter")
+check(first.loads == 1, "first load")
+check(api.loaded("counter"), "loaded")
+check(api.unload("counter") == true, "unload")
+check(not api.loaded("counter"), "unloaded")
+local second = require("counter")
+check(second.loads == 2, "reloaded")
+
+require("lib.util")
+check(require("util") == require("lib.util"), "alias shares the module")
+check(api.loaded("util"), "alias loaded")
+check(api.source_path("util") == "tests/fixtures/introspect/lua/lib/util.lua", "alias source")
+check(api.source_path("counter") == "tests/fixtures/introspect/lua/counter.lua", "source")
+check(api.source_path("missing") == nil, "unknown source")
+
+check(api.version:match("^%d+%.%d+%.%d+"), "version")
+check(#api.build_id == 16 and api.build_id:match("^%x+$"), "build id")
+check(not pcall(function() __LUAPACK.version = "x" end), "read-only")
+check(getmetatable(api) == false, "metatable locked")
@@ -0,0 +1,2 @@
+love = {}
+love.graphics = {}
@@ -0,0 +1,6 @@
+local ui = require("ui")
+local state = require("state")
+
+ui.draw(state.score)
+love.graphics.present()
+print(Config.title)
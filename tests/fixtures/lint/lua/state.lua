@@ -0,0 +1,12 @@
+local M = { score = 0 }
+
+function M:bump()
+  self.score = self.score + 1
+  return helper(self)
+end
+
+repeat
+  local done = true
+until done
+
+return M
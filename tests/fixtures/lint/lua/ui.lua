@@ -0,0 +1,11 @@
+local M = {}
+
+function M.draw(score)
+  local text = string.format("%d", score)
+  for i = 1, #text do
+    drawDigit(text:sub(i, i))
+  end
+  Counter = (Counter or 0) + 1
+end
+
+return M
@@ -0,0 +1 @@
+Config = { title = "lint" }
@@ -0,0 +1,8 @@
+local M = {}
+
+-- Raise an error from a module other than the entry.
+function M.now(reason)
+  error("failed: " .. tostring(reason))
+end
+
+return M
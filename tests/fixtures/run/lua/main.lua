@@ -0,0 +1,5 @@
+local fail = require("app.fail")
+Host.log("started with " .. tostring(arg[1]))
+if arg[1] == "fail" then
+  fail.now(arg[2])
+end
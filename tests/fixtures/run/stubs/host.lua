@@ -0,0 +1,4 @@
+-- Stand-in for the host API the scripts expect.
+Host = {}
+
+function Host.log(msg) end
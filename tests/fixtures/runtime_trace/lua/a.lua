@@ -0,0 +1 @@
+return { value = require("b").value + 1 }
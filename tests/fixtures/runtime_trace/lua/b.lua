@@ -0,0 +1 @@
+return { value = 1 }
@@ -0,0 +1,21 @@
+local lines = {}
+__LUAPACK_TRACE = function(line)
+  lines[#lines + 1] = line
+end
+
+local a = require("a")
+local b = require("b")
+assert(a.value == 2 and b.value == 1)
+
+-- Entries are in call order; the entry's own load is still running.
+local got = {}
+for i, e in ipairs(__LUAPACK_PROFILE) do
+  got[i] = tostring(e.from) .. ">" .. e.name .. (e.cached and "*" or "")
+end
+local want = "nil>__root,__root>a,a>b,__root>b*"
+if table.concat(got, ",") ~= want then
+  error("profile: " .. table.concat(got, ","))
+end
+if #lines ~= 3 or not lines[2]:find("^%[luapack%] __root %-> a: [%d.]+ ms$") then
+  error("trace: " .. table.concat(lines, " | "))
+end
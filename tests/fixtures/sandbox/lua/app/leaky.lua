@@ -0,0 +1,4 @@
+local M = {}
+LEAK = 1
+LEAK = 2
+return M
@@ -0,0 +1,8 @@
+require("app.leaky")
+require("widgets.button")
+assert(rawget(_G, "WG") == "button", "allowed global")
+if rawget(_G, "WARNINGS") then
+  assert(#WARNINGS == 1, table.concat(WARNINGS, "\n"))
+  assert(WARNINGS[1] == "[luapack] module 'app.leaky' assigns global 'LEAK'", WARNINGS[1])
+  assert(rawget(_G, "LEAK") == 2)
+end
@@ -0,0 +1,2 @@
+WG = "button"
+return {}
@@ -0,0 +1,5 @@
+-- Collect sandbox warnings instead of printing them.
+WARNINGS = {}
+__LUAPACK_WARN = function(msg)
+  WARNINGS[#WARNINGS + 1] = msg
+end
@@ -0,0 +1,7 @@
+local util = require("util")
+
+assert(util.double(2) == 4)
+--@debug begin
+print("debug build", util.double(1))
+--@debug end
+return util.double(21)
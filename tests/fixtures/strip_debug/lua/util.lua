@@ -0,0 +1,8 @@
+local M = {}
+
+function M.double(n)
+  assert(type(n) == "number", "number expected")
+  return n * 2
+end
+
+return M
@@ -0,0 +1,9 @@
+local json = require("json")
+local class = require("class")
+local middleclass = require("middleclass")
+local inspect = require("inspect")
+
+assert(json.version == "1.0", "json from the first vendor root")
+assert(class ~= middleclass, "shared loader, separate module values")
+assert(class.new().kind == "object")
+assert(inspect(1) == "1")
@@ -0,0 +1,7 @@
+local M = {}
+
+function M.new()
+  return { kind = "object" }
+end
+
+return M
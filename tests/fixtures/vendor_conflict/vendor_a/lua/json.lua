@@ -0,0 +1,7 @@
+local json = { version = "1.0" }
+
+function json.encode(value)
+  return tostring(value)
+end
+
+return json
@@ -0,0 +1 @@
+return function(v) return tostring(v) end
@@ -0,0 +1,11 @@
+local json = { version = "2.0" }
+
+function json.encode(value)
+  return tostring(value)
+end
+
+function json.decode(text)
+  return text
+end
+
+return json
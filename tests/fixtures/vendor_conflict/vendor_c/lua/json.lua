@@ -0,0 +1,7 @@
+local json = { version = "3.0" }
+
+function json.encode(value)
+  return tostring(value)
+end
+
+return json
@@ -0,0 +1,5 @@
+return {
+  wrap = function(value)
+    return "<" .. tostring(value) .. ">"
+  end,
+}
@@ -0,0 +1,7 @@
+-- A scratch helper that happens to share its name with the vendored library.
+local helper = require("jsonhelper")
+
+return {
+  origin = "local",
+  encode = helper.wrap,
+}
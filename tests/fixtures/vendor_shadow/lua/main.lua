@@ -0,0 +1,8 @@
+-- Expects the vendored json library, not the local file of the same name.
+local json = require("json")
+local same = require("lib.json")
+local inspect = require("inspect")
+
+assert(json.origin == "vendor", "json shadowed by " .. json.origin)
+assert(same == json)
+assert(inspect(json.encode(1)) == "1")
@@ -0,0 +1,78 @@
+use luapack::*;
+
+mod common;
+
+const PATHS: &[&str] = &[
+    "tests/fixtures/aliases/lua/?.lua",
+    "tests/fixtures/aliases/lua/core/?.lua",
+];
+
+#[test]
+fn names_resolving_to_one_file_become_aliases() {
+    let normalizer = NameNormalizer::default();
+    let (_, _, _, graph) =
+        common::build_graph("tests/fixtures/aliases/lua/main.lua", PATHS, &normalizer);
+
+    let mut names: Vec<_> = graph.first_party.keys().cloned().collect();
+    names.sort();
+    assert_eq!(names, ["core.greet", "util"]);
+    assert_eq!(graph.aliases.len(), 1);
+    assert_eq!(graph.aliases["greet"], "core.greet");
+}
+
+#[test]
+fn bundle_maps_aliases_to_one_loader() {
+    let (bundle, _) = common::bundle_for(
+        "tests/fixtures/aliases/lua/main.lua",
+        PATHS,
+        common::BundleOptions {
+            entry_override: None,
+            preludes: &[],
+            replaces: &[],
+            vendor_specs: &[],
+        },
+    );
+    assert_eq!(bundle.matches("__B_MODULES['core.greet'] =").count(), 1);
+    assert!(!bundle.contains("__B_MODULES['greet']"));
+    insta::assert_snapshot!(bundle);
+}
+
+const INIT_PATHS: &[&str] = &[
+    "tests/fixtures/aliases_init/lua/?.lua",
+    "tests/fixtures/aliases_init/lua/?/init.lua",
+];
+
+fn init_bundle() -> (String, BundleStats) {
+    let paths: Vec<String> = INIT_PATHS.iter().map(|p| p.to_string()).collect();
+    let normalizer = NameNormalizer::new(infer_suffixes(&paths, &[], &[], &[]));
+    let (entry, code, resolver, graph) = common::build_graph(
+        "tests/fixtures/aliases_init/lua/main.lua",
+        INIT_PATHS,
+        &normalizer,
+    );
+    assert_eq!(graph.aliases["core.greet.init"], "core.greet");
+    let ctx = common::bundle_ctx(&entry, &code, &resolver, &normalizer);
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
+}
+
+#[test]
+fn required_spellings_that_normalize_away_become_aliases() {
+    let (bundle, _) = init_bundle();
+    assert_eq!(bundle.matches("__B_MODULES['core.greet'] =").count(), 1);
+    assert!(bundle.contains("__B_ALIASES['core.greet.init'] = 'core.greet'"));
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn init_spelling_runs() {
+    let (bundle, stats) = init_bundle();
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &stats.source_map,
+        },
+    )
+    .expect("run");
+}
@@ -0,0 +1,56 @@
+use luapack::*;
+
+mod common;
+
+fn instrumented_bundle(instrument: Option<Instrument>) -> (String, BundleStats) {
+    let normalizer = NameNormalizer::default();
+    let (entry, code, resolver, graph) = common::build_graph(
+        "tests/fixtures/coverage/lua/main.lua",
+        &["tests/fixtures/coverage/lua/?.lua"],
+        &normalizer,
+    );
+    let ctx = BundleCtx {
+        instrument,
+        ..common::bundle_ctx(&entry, &code, &resolver, &normalizer)
+    };
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
+}
+
+#[test]
+fn probes_keep_module_lines() {
+    let (plain, _) = instrumented_bundle(None);
+    assert!(!plain.contains("__LUAPACK_COVERAGE = {"));
+    assert!(!plain.contains("__B_HIT"));
+
+    let (bundle, stats) = instrumented_bundle(Some(Instrument::Coverage));
+    let issues = validate_dialect(&bundle, LuaDialect::Lua51);
+    assert!(issues.is_empty(), "{issues:?}");
+    let lines: Vec<&str> = bundle.lines().collect();
+    let util = stats
+        .source_map
+        .chunks
+        .iter()
+        .find(|c| c.module.as_deref() == Some("util"))
+        .expect("util span");
+    assert_eq!(
+        lines[util.first_line + 5],
+        "    __B_HIT(7); return \"0\"",
+        "line 7 of util.lua stays on line 7"
+    );
+    insta::assert_snapshot!(bundle);
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn lcov_report_maps_to_source_files() {
+    let (bundle, stats) = instrumented_bundle(Some(Instrument::Coverage));
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &stats.source_map,
+        },
+    )
+    .expect("run");
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+mod common;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("luapack-depfile-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("temp dir");
+    dir
+}
+
+#[test]
+fn depfile_lists_the_ignore_file() {
+    let out = temp_dir("ignore");
+    let status = Command::new(env!("CARGO_BIN_EXE_luapack"))
+        .current_dir(common::manifest_path("tests/fixtures/exclude"))
+        .args(["bundle", "lua/main.lua", "--path", "lua/?.lua", "--output"])
+        .arg(out.join("bundle.lua"))
+        .arg("--depfile")
+        .arg(out.join("bundle.d"))
+        .status()
+        .expect("run luapack");
+    assert!(status.success());
+    let depfile = std::fs::read_to_string(out.join("bundle.d")).expect("depfile");
+    assert!(
+        depfile.contains("/tests/fixtures/exclude/.luapackignore"),
+        "{depfile}"
+    );
+    assert!(!depfile.contains("editor"), "{depfile}");
+    let _ = std::fs::remove_dir_all(&out);
+}
+
+#[test]
+fn missing_ignore_file_is_left_out() {
+    let out = temp_dir("no-ignore");
+    let status = Command::new(env!("CARGO_BIN_EXE_luapack"))
+        .current_dir(common::manifest_path("tests/fixtures/include"))
+        .args(["bundle", "lua/main.lua", "--path", "lua/?.lua", "--output"])
+        .arg(out.join("bundle.lua"))
+        .arg("--depfile")
+        .arg(out.join("bundle.d"))
+        .status()
+        .expect("run luapack");
+    assert!(status.success());
+    let depfile = std::fs::read_to_string(out.join("bundle.d")).expect("depfile");
+    assert!(!depfile.contains(".luapackignore"), "{depfile}");
+    let _ = std::fs::remove_dir_all(&out);
+}
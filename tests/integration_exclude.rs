@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use luapack::*;
+
+mod common;
+
+fn excluded_build() -> (String, ModuleResolver, ModuleGraph) {
+    let base = PathBuf::from(common::manifest_path("tests/fixtures/exclude"));
+    let globs = read_ignore_file(&base).expect("ignore file");
+    let resolver = ModuleResolver::new(vec![common::manifest_path(
+        "tests/fixtures/exclude/lua/?.lua",
+    )])
+    .with_excludes(exclude_patterns(&globs, Some(&base)).expect("globs"));
+    let entry = base.join("lua/main.lua");
+    let code = std::fs::read_to_string(&entry).expect("read entry");
+    let normalizer = NameNormalizer::default();
+    let graph = ModuleGraph::build_from_entry_code(&code, &resolver, &normalizer);
+    let ctx = common::bundle_ctx(&entry, &code, &resolver, &normalizer);
+    let (bundle, _) = generate_bundle(&graph, ctx).expect("bundle");
+    (bundle, resolver, graph)
+}
+
+#[test]
+fn excluded_and_ignored_requires_are_not_bundled() {
+    let (bundle, _, graph) = excluded_build();
+    let mut first_party: Vec<&str> = graph.first_party.keys().map(String::as_str).collect();
+    first_party.sort();
+    assert_eq!(first_party, ["app"]);
+    assert_eq!(
+        graph
+            .excluded
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>(),
+        ["editor.tools"]
+    );
+    // The ignored require is not followed at all.
+    assert!(graph.unresolved.is_empty());
+    assert!(graph.edges.iter().all(|e| e.to != "test_helper"));
+
+    assert!(bundle.contains("__B_MODULES['app'] = function("));
+    assert!(!bundle.contains("__B_MODULES['editor.tools']"));
+    assert!(!bundle.contains("__B_MODULES['test_helper']"));
+    assert!(bundle.contains("require(\"test_helper\") -- luapack: ignore"));
+}
+
+#[test]
+fn excluded_modules_are_external() {
+    let (bundle, resolver, graph) = excluded_build();
+    let normalizer = NameNormalizer::default();
+    let export = classify_graph(
+        &graph,
+        &GraphExportCtx {
+            entry_path: Path::new("main.lua"),
+            replaces: &[],
+            vendor_mods: &HashMap::new(),
+            externals: &[],
+            resolver: Some(&resolver),
+            normalizer: &normalizer,
+        },
+    );
+    let tools = export
+        .nodes
+        .iter()
+        .find(|n| n.name == "editor.tools")
+        .expect("node");
+    assert_eq!(tools.kind, ModuleKind::External);
+
+    let report = verify_bundle(
+        &bundle,
+        &VerifyCtx {
+            dialect: LuaDialect::Lua51,
+            replaces: &[],
+            externals: &[],
+            resolver: Some(&resolver),
+            normalizer: &normalizer,
+        },
+    );
+    assert!(report.issues.is_empty(), "{:?}", report.issues);
+}
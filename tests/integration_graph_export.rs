@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use luapack::{
+    classify_graph, collect_vendor_modules, expand_includes, parse_replace_rules,
+    parse_vendor_specs, render_graph, GraphExport, GraphExportCtx, GraphFormat, ModuleGraph,
+    ModuleKind, NameNormalizer, ROOT_MODULE,
+};
+
+mod common;
+
+fn export() -> GraphExport {
+    let normalizer = NameNormalizer::default();
+    let (entry, _, resolver, graph) = common::build_graph(
+        "tests/fixtures/graph_export/lua/main.lua",
+        &["tests/fixtures/graph_export/lua/?.lua"],
+        &normalizer,
+    );
+    let rules = parse_replace_rules(&["match=prefix,prefix=svc.,new=svc_require".to_string()])
+        .expect("parse rules");
+    let specs = parse_vendor_specs(&[common::manifest_path(
+        "tests/fixtures/graph_export/vendor/?.lua",
+    )])
+    .expect("parse vendor");
+    let (vendor_mods, _) = collect_vendor_modules(&specs, &rules, &normalizer).expect("vendor");
+    classify_graph(
+        &graph,
+        &GraphExportCtx {
+            entry_path: &entry,
+            replaces: &rules,
+            vendor_mods: &vendor_mods,
+            externals: &["love.*".to_string()],
+            resolver: Some(&resolver),
+            normalizer: &normalizer,
+        },
+    )
+}
+
+#[test]
+fn graph_records_edges_from_requirer_to_required() {
+    let normalizer = NameNormalizer::default();
+    let (_, _, _, graph) = common::build_graph(
+        "tests/fixtures/graph_export/lua/main.lua",
+        &["tests/fixtures/graph_export/lua/?.lua"],
+        &normalizer,
+    );
+    let edges: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|e| (e.from.as_str(), e.to.as_str()))
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            (ROOT_MODULE, "app.util"),
+            (ROOT_MODULE, "svc.net"),
+            (ROOT_MODULE, "love.graphics"),
+            (ROOT_MODULE, "missing.mod"),
+            ("app.util", "json"),
+            ("app.util", "app.log"),
+        ]
+    );
+    assert!(graph.edges[4].file.ends_with("app/util.lua"));
+}
+
+#[test]
+fn graph_nodes_are_classified() {
+    let export = export();
+    let kinds: Vec<(&str, ModuleKind)> = export
+        .nodes
+        .iter()
+        .map(|n| (n.name.as_str(), n.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (ROOT_MODULE, ModuleKind::FirstParty),
+            ("app.log", ModuleKind::FirstParty),
+            ("app.util", ModuleKind::FirstParty),
+            ("json", ModuleKind::Vendor),
+            ("love.graphics", ModuleKind::External),
+            ("missing.mod", ModuleKind::Unresolved),
+            ("svc.net", ModuleKind::Replaced),
+        ]
+    );
+    let vendor = export.nodes.iter().find(|n| n.name == "json").unwrap();
+    assert!(vendor
+        .path
+        .as_deref()
+        .is_some_and(|p| p.ends_with(PathBuf::from("vendor/json.lua"))));
+}
+
+#[test]
+fn graph_renders_dot_and_mermaid() {
+    let export = export();
+    insta::assert_snapshot!("dot", render_graph(&export, GraphFormat::Dot));
+    insta::assert_snapshot!("mermaid", render_graph(&export, GraphFormat::Mermaid));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&render_graph(&export, GraphFormat::Json)).expect("valid json");
+    assert_eq!(json["edges"][0]["from"], ROOT_MODULE);
+    assert_eq!(json["nodes"][6]["kind"], "replaced");
+}
+
+#[test]
+fn include_roots_are_nodes() {
+    let templates = vec![common::manifest_path("tests/fixtures/include/lua/?.lua")];
+    let normalizer = NameNormalizer::default();
+    let roots =
+        expand_includes(&["themes.**".to_string()], &templates, &normalizer).expect("expand");
+    let entry = PathBuf::from(common::manifest_path("tests/fixtures/include/lua/main.lua"));
+    let code = std::fs::read_to_string(&entry).expect("read entry");
+    let resolver = common::mk_resolver(templates);
+    let graph = ModuleGraph::build_with_roots(&code, &roots, &resolver, &normalizer, None);
+    let export = classify_graph(
+        &graph,
+        &GraphExportCtx {
+            entry_path: &entry,
+            replaces: &[],
+            vendor_mods: &HashMap::new(),
+            externals: &[],
+            resolver: Some(&resolver),
+            normalizer: &normalizer,
+        },
+    );
+    let names: Vec<&str> = export.nodes.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            ROOT_MODULE,
+            "themes.dark",
+            "themes.extra.neon",
+            "util.colors"
+        ]
+    );
+    assert!(export
+        .nodes
+        .iter()
+        .all(|n| n.kind == ModuleKind::FirstParty));
+    assert_eq!(
+        render_graph(&export, GraphFormat::Mermaid)
+            .lines()
+            .filter(|l| l.contains("-->"))
+            .collect::<Vec<_>>(),
+        ["  n1 --> n3"]
+    );
+    assert_eq!(
+        graph.include_chain("util.colors"),
+        Some(vec!["themes.dark".to_string(), "util.colors".to_string()])
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use luapack::NameNormalizer;
+
+const PATHS: &[&str] = &["tests/fixtures/graph_levels/lua/?.lua"];
+
+#[test]
+fn graph_keeps_one_parsed_unit_per_module() {
+    let normalizer = NameNormalizer::default();
+    let (_, _, _, graph) = common::build_graph(
+        "tests/fixtures/graph_levels/lua/main.lua",
+        PATHS,
+        &normalizer,
+    );
+
+    let mut names: Vec<_> = graph.first_party.keys().cloned().collect();
+    names.sort();
+    assert_eq!(names, ["lib.a", "lib.b", "lib.c", "lib.d"]);
+    assert_eq!(graph.units.len(), 4);
+
+    let c = &graph.units[&graph.first_party["lib.c"]];
+    let requires: Vec<_> = c.requires.iter().map(|r| r.module.as_str()).collect();
+    assert_eq!(requires, ["lib.d"]);
+    assert!(c.source.contains("value = d"));
+
+    let entry = graph.entry.as_ref().expect("entry unit");
+    assert_eq!(entry.requires.len(), 2);
+}
+
+#[test]
+fn parallel_bundle_is_deterministic() {
+    let run = || {
+        common::bundle_for(
+            "tests/fixtures/graph_levels/lua/main.lua",
+            PATHS,
+            common::BundleOptions {
+                entry_override: None,
+                preludes: &[],
+                replaces: &[],
+                vendor_specs: &[],
+            },
+        )
+        .0
+    };
+    let bundle = run();
+    assert_eq!(bundle, run());
+    insta::assert_snapshot!(bundle);
+}
@@ -0,0 +1,89 @@
+use luapack::*;
+
+mod common;
+
+const PATHS: &[&str] = &["tests/fixtures/hot_reload/lua/?.lua"];
+/// `app.model` edited: the v2 copy shadows the original.
+const PATHS_V2: &[&str] = &[
+    "tests/fixtures/hot_reload/v2/?.lua",
+    "tests/fixtures/hot_reload/lua/?.lua",
+];
+
+fn with_ctx<T>(
+    paths: &[&str],
+    hot_reload: bool,
+    f: impl FnOnce(&ModuleGraph, BundleCtx) -> T,
+) -> T {
+    let normalizer = NameNormalizer::default();
+    let (entry, code, resolver, graph) =
+        common::build_graph("tests/fixtures/hot_reload/lua/main.lua", paths, &normalizer);
+    let ctx = BundleCtx {
+        hot_reload,
+        ..common::bundle_ctx(&entry, &code, &resolver, &normalizer)
+    };
+    f(&graph, ctx)
+}
+
+fn bundle(hot_reload: bool) -> String {
+    with_ctx(PATHS, hot_reload, |graph, ctx| {
+        generate_bundle_with_stats(graph, ctx).expect("bundle").0
+    })
+}
+
+fn hot_update(modules: &[&str]) -> anyhow::Result<String> {
+    let modules: Vec<String> = modules.iter().map(|m| m.to_string()).collect();
+    with_ctx(PATHS_V2, true, |graph, ctx| {
+        generate_hot_update(graph, &ctx, &modules)
+    })
+}
+
+#[test]
+fn header_embeds_dependencies_only_when_enabled() {
+    assert!(!bundle(false).contains("__LUAPACK_HOT"));
+
+    let code = bundle(true);
+    let issues = validate_dialect(&code, LuaDialect::Lua51);
+    assert!(issues.is_empty(), "{issues:?}");
+    assert!(code.contains("  ['__root'] = { 'app.other', 'app.view' },\n"));
+    assert!(code.contains("  ['app.view'] = { 'app.model' },\n"));
+}
+
+#[test]
+fn hot_update_registers_changed_modules() {
+    let update = hot_update(&["app.model"]).expect("update");
+    assert!(validate_dialect(&update, LuaDialect::Lua51).is_empty());
+    insta::assert_snapshot!(update);
+}
+
+#[test]
+fn hot_update_rejects_unknown_modules() {
+    let err = hot_update(&["app.missing"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "module 'app.missing' is not part of the bundle"
+    );
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn reload_re_requires_dependents_in_dependency_order() {
+    // The update runs in the same Lua state as the bundle, as a host would load it.
+    let program = format!(
+        "(function(...)\n{}\nend)()\nlocal reloaded = (function(...)\n{}\nend)()\n{}",
+        bundle(true),
+        hot_update(&["app.model"]).expect("update"),
+        r#"assert(table.concat(reloaded, ",") == "app.model,app.view", table.concat(reloaded, ","))
+assert(MODEL_LOADS == 2 and VIEW_TEXT == "view:v2", VIEW_TEXT)
+assert(OTHER_LOADS == 1)
+"#
+    );
+    run_bundle(
+        &program,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &SourceMap::default(),
+        },
+    )
+    .expect("run");
+}
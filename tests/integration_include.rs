@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use luapack::*;
+
+mod common;
+
+fn included_graph(globs: &[&str]) -> (PathBuf, String, ModuleResolver, ModuleGraph) {
+    let templates = vec![common::manifest_path("tests/fixtures/include/lua/?.lua")];
+    let normalizer = NameNormalizer::default();
+    let globs: Vec<String> = globs.iter().map(|g| g.to_string()).collect();
+    let roots = expand_includes(&globs, &templates, &normalizer).expect("expand");
+    let entry = PathBuf::from(common::manifest_path("tests/fixtures/include/lua/main.lua"));
+    let code = std::fs::read_to_string(&entry).expect("read entry");
+    let resolver = common::mk_resolver(templates);
+    let graph = ModuleGraph::build_with_roots(&code, &roots, &resolver, &normalizer, None);
+    (entry, code, resolver, graph)
+}
+
+fn bundle(graph: &ModuleGraph, entry: &Path, code: &str, resolver: &ModuleResolver) -> String {
+    let normalizer = NameNormalizer::default();
+    let ctx = common::bundle_ctx(entry, code, resolver, &normalizer);
+    generate_bundle(graph, ctx).expect("bundle").0
+}
+
+#[test]
+fn dynamic_requires_reach_nothing_without_includes() {
+    let (_, _, _, graph) = included_graph(&[]);
+    assert!(graph.first_party.is_empty());
+}
+
+#[test]
+fn included_modules_bring_their_dependencies() {
+    let (entry, code, resolver, graph) = included_graph(&["widgets.*", "themes.*"]);
+    let names: HashSet<&str> = graph.first_party.keys().map(String::as_str).collect();
+    assert_eq!(
+        names,
+        HashSet::from([
+            "widgets.button",
+            "widgets.label",
+            "themes.dark",
+            "util.colors"
+        ])
+    );
+    // Only the literal require inside themes.dark is an edge.
+    let edges: Vec<(&str, &str)> = graph
+        .edges
+        .iter()
+        .map(|e| (e.from.as_str(), e.to.as_str()))
+        .collect();
+    assert_eq!(edges, [("themes.dark", "util.colors")]);
+
+    let bundle = bundle(&graph, &entry, &code, &resolver);
+    for name in names {
+        assert!(
+            bundle.contains(&format!("__B_MODULES['{name}'] = function(")),
+            "{name} is bundled"
+        );
+    }
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn dynamic_requires_load_included_modules() {
+    let (entry, code, resolver, graph) = included_graph(&["widgets.*", "themes.*"]);
+    let bundle = bundle(&graph, &entry, &code, &resolver);
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &SourceMap::default(),
+        },
+    )
+    .expect("run");
+}
@@ -0,0 +1,71 @@
+use luapack::*;
+
+mod common;
+
+const PATHS: &[&str] = &[
+    "tests/fixtures/introspect/lua/?.lua",
+    "tests/fixtures/introspect/lua/lib/?.lua",
+];
+
+fn introspect_bundle(introspect: bool, entry_code: Option<&str>) -> (String, BundleStats) {
+    let normalizer = NameNormalizer::default();
+    let (entry, mut code, resolver, graph) =
+        common::build_graph("tests/fixtures/introspect/lua/main.lua", PATHS, &normalizer);
+    if let Some(c) = entry_code {
+        code = c.to_string();
+    }
+    let ctx = BundleCtx {
+        introspect,
+        ..common::bundle_ctx(&entry, &code, &resolver, &normalizer)
+    };
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
+}
+
+fn header(bundle: &str) -> &str {
+    &bundle[..bundle.find("-- module:").unwrap()]
+}
+
+#[test]
+fn header_exposes_api_only_when_enabled() {
+    let (plain, _) = introspect_bundle(false, None);
+    assert!(!plain.contains("__LUAPACK = setmetatable"));
+
+    let (bundle, _) = introspect_bundle(true, None);
+    let issues = validate_dialect(&bundle, LuaDialect::Lua51);
+    assert!(issues.is_empty(), "{issues:?}");
+    let build_id = bundle
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("build_id = '"))
+        .and_then(|l| l.strip_suffix("',"))
+        .expect("build_id");
+    assert_eq!(build_id.len(), 16);
+    insta::assert_snapshot!(header(&bundle).replace(build_id, "<build id>"));
+}
+
+#[test]
+fn build_id_follows_bundle_content() {
+    let id = |bundle: &str| {
+        let at = bundle.find("build_id = '").unwrap() + "build_id = '".len();
+        bundle[at..at + 16].to_string()
+    };
+    let (a, _) = introspect_bundle(true, None);
+    let (b, _) = introspect_bundle(true, None);
+    let (c, _) = introspect_bundle(true, Some("return require('counter')\n"));
+    assert_eq!(id(&a), id(&b));
+    assert_ne!(id(&a), id(&c));
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn api_reports_and_unloads_modules() {
+    let (bundle, stats) = introspect_bundle(true, None);
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &stats.source_map,
+        },
+    )
+    .expect("run");
+}
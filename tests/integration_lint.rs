@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use luapack::{lint_globals, LintCtx, LintSource, LuaDialect, NameNormalizer};
+
+mod common;
+
+fn source(module: Option<&str>, path: &str) -> LintSource {
+    let path = PathBuf::from(common::manifest_path(path));
+    LintSource {
+        module: module.map(str::to_string),
+        code: std::fs::read_to_string(&path).expect("read source"),
+        path,
+    }
+}
+
+/// `(file name, line, col, message)` for every issue in the fixture project.
+fn lint_fixture(host_globals: &[String], library: bool) -> Vec<(String, usize, usize, String)> {
+    let normalizer = NameNormalizer::new(HashSet::new());
+    let (entry, code, _, graph) = common::build_graph(
+        "tests/fixtures/lint/lua/main.lua",
+        &["tests/fixtures/lint/lua/?.lua"],
+        &normalizer,
+    );
+    let mut sources = vec![LintSource {
+        module: None,
+        path: entry,
+        code,
+    }];
+    let mut modules: Vec<_> = graph.first_party.iter().collect();
+    modules.sort();
+    for (name, path) in modules {
+        sources.push(source(Some(name), &path.to_string_lossy()));
+    }
+    let mut providers = vec![source(None, "tests/fixtures/lint/preludes/config.lua")];
+    if library {
+        providers.push(source(None, "tests/fixtures/lint/library/love.lua"));
+    }
+
+    let files = lint_globals(
+        &sources,
+        &LintCtx {
+            dialect: LuaDialect::Lua51,
+            host_globals,
+            providers: &providers,
+        },
+    );
+    files
+        .into_iter()
+        .flat_map(|f| {
+            let name = f.path.file_name().unwrap().to_string_lossy().into_owned();
+            f.issues
+                .into_iter()
+                .map(move |i| (name.clone(), i.line, i.col, i.message))
+        })
+        .collect()
+}
+
+fn issue(file: &str, line: usize, col: usize, message: &str) -> (String, usize, usize, String) {
+    (file.to_string(), line, col, message.to_string())
+}
+
+#[test]
+fn reports_undefined_reads_and_module_global_writes() {
+    assert_eq!(
+        lint_fixture(&[], true),
+        [
+            issue("state.lua", 5, 10, "undefined global 'helper'"),
+            issue("ui.lua", 6, 5, "undefined global 'drawDigit'"),
+            issue("ui.lua", 8, 3, "module 'ui' assigns global 'Counter'"),
+        ]
+    );
+}
+
+#[test]
+fn library_stubs_and_host_globals_define_globals() {
+    let issues = lint_fixture(&[], false);
+    assert!(issues.contains(&issue("main.lua", 5, 1, "undefined global 'love'")));
+
+    let host = vec![
+        "love".to_string(),
+        "helper".to_string(),
+        "drawDigit".to_string(),
+    ];
+    assert_eq!(
+        lint_fixture(&host, false),
+        [issue(
+            "ui.lua",
+            8,
+            3,
+            "module 'ui' assigns global 'Counter'"
+        )]
+    );
+}
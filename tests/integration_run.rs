@@ -0,0 +1,63 @@
+#![cfg(feature = "run")]
+
+use std::path::PathBuf;
+
+use luapack::{run_bundle, RunCtx};
+
+mod common;
+
+fn run(args: &[&str], stubs: &[&str]) -> anyhow::Result<()> {
+    let (bundle, stats) = common::bundle_with_stats_for(
+        "tests/fixtures/run/lua/main.lua",
+        &["tests/fixtures/run/lua/?.lua"],
+        common::BundleOptions {
+            entry_override: None,
+            preludes: &[],
+            replaces: &[],
+            vendor_specs: &[],
+        },
+    );
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let stubs: Vec<PathBuf> = stubs
+        .iter()
+        .map(|s| PathBuf::from(common::manifest_path(s)))
+        .collect();
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &args,
+            stubs: &stubs,
+            source_map: &stats.source_map,
+        },
+    )
+}
+
+#[test]
+fn runs_with_stubs_and_args() {
+    run(&["ok"], &["tests/fixtures/run/stubs/host.lua"]).expect("run");
+}
+
+#[test]
+fn errors_point_at_the_original_module() {
+    let err = run(
+        &["fail", "disk full"],
+        &["tests/fixtures/run/stubs/host.lua"],
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err.starts_with(&format!(
+            "{}:5: failed: disk full",
+            common::manifest_path("tests/fixtures/run/lua/app/fail.lua")
+        )),
+        "{err}"
+    );
+    assert!(err.contains("tests/fixtures/run/lua/main.lua:4:"), "{err}");
+}
+
+#[test]
+fn missing_stubs_fail_in_the_entry() {
+    let err = run(&["ok"], &[]).unwrap_err().to_string();
+    assert!(err.contains("tests/fixtures/run/lua/main.lua:2:"), "{err}");
+    assert!(err.contains("Host"), "{err}");
+}
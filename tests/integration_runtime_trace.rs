@@ -0,0 +1,44 @@
+use luapack::*;
+
+mod common;
+
+fn traced_bundle(runtime_trace: bool) -> (String, BundleStats) {
+    let normalizer = NameNormalizer::default();
+    let (entry, code, resolver, graph) = common::build_graph(
+        "tests/fixtures/runtime_trace/lua/main.lua",
+        &["tests/fixtures/runtime_trace/lua/?.lua"],
+        &normalizer,
+    );
+    let ctx = BundleCtx {
+        runtime_trace,
+        ..common::bundle_ctx(&entry, &code, &resolver, &normalizer)
+    };
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
+}
+
+#[test]
+fn header_collects_profile_only_when_enabled() {
+    let (plain, _) = traced_bundle(false);
+    assert!(!plain.contains("__LUAPACK_PROFILE = {}"));
+    assert!(!plain.contains("__B_TRACE"));
+
+    let (traced, _) = traced_bundle(true);
+    let issues = validate_dialect(&traced, LuaDialect::Lua51);
+    assert!(issues.is_empty(), "{issues:?}");
+    insta::assert_snapshot!(traced);
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn profile_records_requester_and_cache_hits() {
+    let (bundle, stats) = traced_bundle(true);
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &stats.source_map,
+        },
+    )
+    .expect("run");
+}
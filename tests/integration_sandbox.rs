@@ -0,0 +1,94 @@
+#[cfg(feature = "run")]
+use std::path::PathBuf;
+
+use luapack::*;
+
+mod common;
+
+fn sandboxed_bundle(sandbox: Option<&Sandbox>, dialect: LuaDialect) -> (String, BundleStats) {
+    let normalizer = NameNormalizer::default();
+    let (entry, code, resolver, graph) = common::build_graph(
+        "tests/fixtures/sandbox/lua/main.lua",
+        &["tests/fixtures/sandbox/lua/?.lua"],
+        &normalizer,
+    );
+    let ctx = BundleCtx {
+        sandbox,
+        dialect,
+        ..common::bundle_ctx(&entry, &code, &resolver, &normalizer)
+    };
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
+}
+
+fn sandbox(mode: SandboxMode) -> Sandbox {
+    Sandbox {
+        mode,
+        allow: parse_sandbox_allow(&["widgets.=WG".to_string()]).unwrap(),
+    }
+}
+
+#[test]
+fn loaders_take_an_environment_only_when_sandboxed() {
+    let (plain, _) = sandboxed_bundle(None, LuaDialect::Lua51);
+    assert!(!plain.contains("__B_ENV"));
+    assert!(plain.contains("__B_MODULES['app.leaky'] = function(require)\n"));
+
+    let rules = sandbox(SandboxMode::Error);
+    let (code, _) = sandboxed_bundle(Some(&rules), LuaDialect::Lua51);
+    assert!(code.contains("__B_MODULES['app.leaky'] = function(require, _ENV)\n"));
+    assert!(code.contains("__B_MODULES['__root'] = function(require, _ENV)\n"));
+    assert!(code.contains("  { prefix = 'widgets.', globals = { 'WG' } },\n"));
+    for dialect in [LuaDialect::Lua51, LuaDialect::Lua54] {
+        let issues = validate_dialect(&code, dialect);
+        assert!(issues.is_empty(), "{dialect}: {issues:?}");
+    }
+}
+
+#[test]
+fn warn_mode_header_snapshot() {
+    let rules = sandbox(SandboxMode::Warn);
+    let (code, _) = sandboxed_bundle(Some(&rules), LuaDialect::Lua51);
+    let header = &code[..code.find("-- module:").unwrap()];
+    insta::assert_snapshot!(header);
+}
+
+#[cfg(feature = "run")]
+fn run(mode: SandboxMode, stubs: &[&str]) -> anyhow::Result<()> {
+    let rules = sandbox(mode);
+    let (code, stats) = sandboxed_bundle(Some(&rules), vm_dialect());
+    let stubs: Vec<PathBuf> = stubs
+        .iter()
+        .map(|s| PathBuf::from(common::manifest_path(s)))
+        .collect();
+    run_bundle(
+        &code,
+        &RunCtx {
+            args: &[],
+            stubs: &stubs,
+            source_map: &stats.source_map,
+        },
+    )
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn error_mode_rejects_global_assignments() {
+    let err = run(SandboxMode::Error, &[]).unwrap_err().to_string();
+    assert!(
+        err.starts_with(&format!(
+            "{}:2: module 'app.leaky' assigns global 'LEAK'",
+            common::manifest_path("tests/fixtures/sandbox/lua/app/leaky.lua")
+        )),
+        "{err}"
+    );
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn warn_mode_logs_once_and_assigns() {
+    run(
+        SandboxMode::Warn,
+        &["tests/fixtures/sandbox/stubs/collect_warnings.lua"],
+    )
+    .expect("run");
+}
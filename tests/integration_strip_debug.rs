@@ -0,0 +1,35 @@
+use luapack::*;
+
+mod common;
+
+fn stripped_bundle(strip_asserts: bool, strip_marked: bool) -> (String, BundleStats) {
+    let normalizer = NameNormalizer::default();
+    let (entry, code, resolver, graph) = common::build_graph(
+        "tests/fixtures/strip_debug/lua/main.lua",
+        &["tests/fixtures/strip_debug/lua/?.lua"],
+        &normalizer,
+    );
+    let ctx = BundleCtx {
+        strip_asserts,
+        strip_marked,
+        ..common::bundle_ctx(&entry, &code, &resolver, &normalizer)
+    };
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
+}
+
+#[test]
+fn removes_asserts_and_marked_regions_from_modules() {
+    let (plain, stats) = stripped_bundle(false, false);
+    assert_eq!(stats.debug_stripped, 0);
+    assert!(plain.contains("assert(type(n) == \"number\""));
+
+    let (asserts_only, stats) = stripped_bundle(true, false);
+    assert_eq!(stats.debug_stripped, 2);
+    assert!(!asserts_only.contains("assert("));
+    assert!(asserts_only.contains("print(\"debug build\""));
+
+    let (bundle, stats) = stripped_bundle(true, true);
+    assert_eq!(stats.debug_stripped, 3);
+    assert!(!bundle.contains("debug build"));
+    assert_eq!(bundle.lines().count(), plain.lines().count());
+}
@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use luapack::{find_unused_modules, ModuleGraph, NameNormalizer};
+
+mod common;
+
+const TEMPLATES: &[&str] = &[
+    "tests/fixtures/unused/lua/?.lua",
+    "tests/fixtures/unused/lua/?/init.lua",
+];
+
+fn unused_names(extra_roots: &[&str]) -> Vec<String> {
+    let normalizer = NameNormalizer::new(HashSet::from(["init".to_string()]));
+    let (entry, _, resolver, graph) =
+        common::build_graph("tests/fixtures/unused/lua/main.lua", TEMPLATES, &normalizer);
+    let mut used: Vec<PathBuf> = vec![entry];
+    used.extend(graph.first_party.into_values());
+    for root in extra_roots {
+        let root = PathBuf::from(common::manifest_path(root));
+        let code = std::fs::read_to_string(&root).expect("read root");
+        let graph = ModuleGraph::build_from_entry_code(&code, &resolver, &normalizer);
+        used.push(root);
+        used.extend(graph.first_party.into_values());
+    }
+    let templates: Vec<String> = TEMPLATES.iter().map(|t| common::manifest_path(t)).collect();
+    find_unused_modules(&templates, &normalizer, &used)
+        .into_iter()
+        .map(|m| m.name)
+        .collect()
+}
+
+#[test]
+fn lists_files_never_required_from_the_entry() {
+    assert_eq!(unused_names(&[]), ["b", "c", "old", "plugins.p"]);
+}
+
+#[test]
+fn extra_roots_count_as_entries() {
+    assert_eq!(
+        unused_names(&["tests/fixtures/unused/lua/plugins/p.lua"]),
+        ["b", "old"]
+    );
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use luapack::*;
+
+mod common;
+
+const ROOTS: &[&str] = &[
+    "tests/fixtures/vendor_conflict/vendor_a/lua/?.lua",
+    "tests/fixtures/vendor_conflict/vendor_b/lua/?.lua",
+];
+
+fn vendor(policy: VendorConflictPolicy) -> anyhow::Result<VendorModules> {
+    vendor_from(ROOTS, policy)
+}
+
+fn vendor_from(roots: &[&str], policy: VendorConflictPolicy) -> anyhow::Result<VendorModules> {
+    let flags: Vec<String> = roots.iter().map(|r| common::manifest_path(r)).collect();
+    let specs = parse_vendor_specs(&flags)?;
+    collect_vendor_set(&specs, &[], &NameNormalizer::default(), policy)
+}
+
+fn bundle(vendor_mods: &HashMap<String, PathBuf>) -> (String, BundleStats) {
+    let normalizer = NameNormalizer::default();
+    let (entry, code, resolver, graph) = common::build_graph(
+        "tests/fixtures/vendor_conflict/lua/main.lua",
+        &["tests/fixtures/vendor_conflict/lua/?.lua"],
+        &normalizer,
+    );
+    let ctx = BundleCtx {
+        vendor_mods,
+        ..common::bundle_ctx(&entry, &code, &resolver, &normalizer)
+    };
+    generate_bundle_with_stats(&graph, ctx).expect("bundle")
+}
+
+#[test]
+fn differing_files_are_conflicts_identical_ones_duplicates() {
+    let found = vendor(VendorConflictPolicy::First).expect("collect");
+    assert_eq!(found.duplicates.iter().collect::<Vec<_>>(), ["inspect"]);
+    assert_eq!(found.conflicts.len(), 1);
+    let conflict = &found.conflicts[0];
+    assert_eq!(conflict.name, "json");
+    assert!(conflict.kept.ends_with("vendor_a/lua/json.lua"));
+    assert!(conflict.dropped.ends_with("vendor_b/lua/json.lua"));
+    assert_eq!(
+        conflict.summary,
+        "7 vs 11 lines; first difference at line 1: `local json = { version = \"1.0\" }` vs \
+         `local json = { version = \"2.0\" }`; -1 +5 lines"
+    );
+    assert_eq!(found.modules["json"], conflict.kept);
+}
+
+#[test]
+fn conflict_policy_picks_the_winner() {
+    let found = vendor(VendorConflictPolicy::Last).expect("collect");
+    assert!(found.modules["json"].ends_with("vendor_b/lua/json.lua"));
+    assert!(found.conflicts[0]
+        .dropped
+        .ends_with("vendor_a/lua/json.lua"));
+
+    let err = vendor(VendorConflictPolicy::Error).unwrap_err().to_string();
+    assert!(
+        err.starts_with("1 vendor module conflict(s):\n  vendor module 'json' differs between ")
+    );
+    assert!(err.contains("vendor_a/lua/json.lua"), "{err}");
+    assert!(err.contains("vendor_b/lua/json.lua"), "{err}");
+}
+
+#[test]
+fn every_conflict_names_the_final_winner() {
+    let roots = [
+        ROOTS[0],
+        ROOTS[1],
+        "tests/fixtures/vendor_conflict/vendor_c/lua/?.lua",
+    ];
+    let found = vendor_from(&roots, VendorConflictPolicy::Last).expect("collect");
+    assert!(found.modules["json"].ends_with("vendor_c/lua/json.lua"));
+    let pairs: Vec<(bool, bool)> = found
+        .conflicts
+        .iter()
+        .map(|c| {
+            (
+                c.kept == found.modules["json"],
+                c.summary
+                    .contains("`local json = { version = \"3.0\" }` vs"),
+            )
+        })
+        .collect();
+    assert_eq!(pairs, [(true, true), (true, true)]);
+    assert!(found.conflicts[0]
+        .dropped
+        .ends_with("vendor_a/lua/json.lua"));
+    assert!(found.conflicts[1]
+        .dropped
+        .ends_with("vendor_b/lua/json.lua"));
+}
+
+#[test]
+fn identical_vendor_files_share_a_loader() {
+    let found = vendor(VendorConflictPolicy::First).expect("collect");
+    let (bundle, stats) = bundle(&found.modules);
+    assert_eq!(
+        stats.vendor_shared,
+        [("middleclass".to_string(), "class".to_string())]
+    );
+    assert!(bundle.contains("__B_MODULES['middleclass'] = __B_MODULES['class']\n"));
+
+    let report = verify_bundle(
+        &bundle,
+        &VerifyCtx {
+            dialect: LuaDialect::Lua51,
+            replaces: &[],
+            externals: &[],
+            resolver: None,
+            normalizer: &NameNormalizer::default(),
+        },
+    );
+    assert!(report.issues.is_empty(), "{:?}", report.issues);
+    assert!(report.modules.contains(&"middleclass".to_string()));
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn shared_loaders_keep_separate_module_values() {
+    let found = vendor(VendorConflictPolicy::First).expect("collect");
+    let (bundle, stats) = bundle(&found.modules);
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &stats.source_map,
+        },
+    )
+    .expect("run");
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use luapack::*;
+
+mod common;
+
+fn graph_and_vendor() -> (
+    PathBuf,
+    String,
+    ModuleResolver,
+    ModuleGraph,
+    HashMap<String, PathBuf>,
+) {
+    let normalizer = NameNormalizer::default();
+    let (entry, code, resolver, graph) = common::build_graph(
+        "tests/fixtures/vendor_shadow/lua/main.lua",
+        &[
+            "tests/fixtures/vendor_shadow/lua/?.lua",
+            "tests/fixtures/vendor_shadow/lua/lib/?.lua",
+        ],
+        &normalizer,
+    );
+    let specs = parse_vendor_specs(&[common::manifest_path(
+        "tests/fixtures/vendor_shadow/vendor/lua/?.lua",
+    )])
+    .expect("specs");
+    let (vendor_mods, _) = collect_vendor_modules(&specs, &[], &normalizer).expect("vendor");
+    (entry, code, resolver, graph, vendor_mods)
+}
+
+fn bundle(
+    graph: &ModuleGraph,
+    entry: &std::path::Path,
+    code: &str,
+    resolver: &ModuleResolver,
+    vendor_mods: &HashMap<String, PathBuf>,
+) -> (String, BundleStats) {
+    let normalizer = NameNormalizer::default();
+    let ctx = BundleCtx {
+        vendor_mods,
+        ..common::bundle_ctx(entry, code, resolver, &normalizer)
+    };
+    generate_bundle_with_stats(graph, ctx).expect("bundle")
+}
+
+#[test]
+fn shadowed_vendor_modules_are_found_with_both_paths() {
+    let (_, _, _, graph, vendor_mods) = graph_and_vendor();
+    let shadows = find_vendor_shadows(&graph, &vendor_mods);
+    assert_eq!(shadows.len(), 1);
+    let s = &shadows[0];
+    assert_eq!(s.name, "json");
+    assert!(s.first_party.ends_with("vendor_shadow/lua/lib/json.lua"));
+    assert!(s.vendor.ends_with("vendor_shadow/vendor/lua/json.lua"));
+    assert_eq!(s.aliases, ["lib.json"]);
+    let shown = s.to_string();
+    assert!(shown.starts_with("first-party module 'json' ("), "{shown}");
+    assert!(shown.ends_with(", also required as lib.json"), "{shown}");
+}
+
+#[test]
+fn first_party_wins_by_default() {
+    let (entry, code, resolver, mut graph, vendor_mods) = graph_and_vendor();
+    let shadows =
+        apply_vendor_shadows(&mut graph, &vendor_mods, VendorShadowPolicy::default()).unwrap();
+    assert_eq!(shadows.len(), 1);
+    assert!(graph.first_party.contains_key("json"));
+    let (bundle, _) = bundle(&graph, &entry, &code, &resolver, &vendor_mods);
+    assert!(
+        bundle.contains("-- module: json  (from tests/fixtures/vendor_shadow/lua/lib/json.lua)")
+    );
+    assert!(!bundle.contains("-- vendor module: json"));
+    assert!(bundle.contains("-- vendor module: inspect"));
+}
+
+#[test]
+fn prefer_vendor_drops_the_first_party_module() {
+    let (entry, code, resolver, mut graph, vendor_mods) = graph_and_vendor();
+    apply_vendor_shadows(&mut graph, &vendor_mods, VendorShadowPolicy::Vendor).unwrap();
+    assert!(!graph.first_party.contains_key("json"));
+    // What only the dropped module required goes with it; its other names now lead to
+    // the vendor file.
+    assert!(!graph.first_party.contains_key("jsonhelper"));
+    assert!(graph.units.is_empty());
+    assert!(graph.edges.iter().all(|e| e.from == ROOT_MODULE));
+    assert_eq!(
+        graph.aliases.get("lib.json").map(String::as_str),
+        Some("json")
+    );
+    let (bundle, _) = bundle(&graph, &entry, &code, &resolver, &vendor_mods);
+    assert!(bundle.contains(
+        "-- vendor module: json  (from tests/fixtures/vendor_shadow/vendor/lua/json.lua)"
+    ));
+    assert!(!bundle.contains("-- module: json"));
+    assert!(!bundle.contains("jsonhelper"));
+}
+
+#[test]
+fn shadowing_can_be_an_error() {
+    let (_, _, _, mut graph, vendor_mods) = graph_and_vendor();
+    let err = apply_vendor_shadows(&mut graph, &vendor_mods, VendorShadowPolicy::Error)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.starts_with(
+            "1 first-party module(s) shadow vendor modules:\n  first-party module 'json'"
+        ),
+        "{err}"
+    );
+    assert!(err.contains("vendor_shadow/lua/lib/json.lua"), "{err}");
+    assert!(err.contains("vendor_shadow/vendor/lua/json.lua"), "{err}");
+}
+
+#[cfg(feature = "run")]
+#[test]
+fn prefer_vendor_runs_the_vendor_file() {
+    let (entry, code, resolver, mut graph, vendor_mods) = graph_and_vendor();
+    apply_vendor_shadows(&mut graph, &vendor_mods, VendorShadowPolicy::Vendor).unwrap();
+    let (bundle, stats) = bundle(&graph, &entry, &code, &resolver, &vendor_mods);
+    run_bundle(
+        &bundle,
+        &RunCtx {
+            args: &[],
+            stubs: &[],
+            source_map: &stats.source_map,
+        },
+    )
+    .expect("run");
+}
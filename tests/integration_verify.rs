@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use luapack::{
+    parse_replace_rules, verify_bundle, BundleReport, LuaDialect, NameNormalizer, ReplaceRule,
+    VerifyCtx,
+};
+
+mod common;
+
+fn bundle(fixture: &str, replaces: &[ReplaceRule]) -> String {
+    common::bundle_for(
+        &format!("tests/fixtures/{}/lua/main.lua", fixture),
+        &[&format!("tests/fixtures/{}/lua/?.lua", fixture)],
+        common::BundleOptions {
+            entry_override: None,
+            preludes: &[],
+            replaces,
+            vendor_specs: &[],
+        },
+    )
+    .0
+}
+
+fn verify(code: &str, replaces: &[ReplaceRule], externals: &[&str]) -> BundleReport {
+    let externals: Vec<String> = externals.iter().map(|s| s.to_string()).collect();
+    verify_bundle(
+        code,
+        &VerifyCtx {
+            dialect: LuaDialect::Lua51,
+            replaces,
+            externals: &externals,
+            resolver: None,
+            normalizer: &NameNormalizer::new(HashSet::new()),
+        },
+    )
+}
+
+fn messages(report: &BundleReport) -> Vec<&str> {
+    report.issues.iter().map(|i| i.message.as_str()).collect()
+}
+
+#[test]
+fn generated_bundle_verifies() {
+    let code = bundle("graph_levels", &[]);
+    let report = verify(&code, &[], &[]);
+    assert!(report.issues.is_empty(), "{:?}", report.issues);
+    assert_eq!(
+        report.modules,
+        ["lib.a", "lib.b", "lib.c", "lib.d", "__root"]
+    );
+}
+
+#[test]
+fn unbundled_requires_need_a_rule_or_external() {
+    let rules = parse_replace_rules(&[
+        "match=prefix,prefix=svc.,new=svc_require".to_string(),
+        "match=exact,name=json,new=require".to_string(),
+    ])
+    .expect("parse rules");
+    let code = bundle("graph_export", &rules);
+
+    let report = verify(&code, &rules, &["love.*"]);
+    assert_eq!(
+        messages(&report),
+        ["require(\"missing.mod\") in module '__root' is not bundled, replaced or external"]
+    );
+
+    let report = verify(&code, &rules[..1], &[]);
+    assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+    assert!(messages(&report)[0].contains("json"));
+}
+
+#[test]
+fn manual_edits_are_caught() {
+    let code = bundle("graph_levels", &[])
+        .replace("__B_MODULES['lib.d']", "__B_MODULES['lib.dd']")
+        .replace(
+            "return __B_REQUIRE('__root')",
+            "__B_ALIASES['x'] = 'nowhere'\nreturn __B_REQUIRE('main')",
+        );
+    let report = verify(&code, &[], &[]);
+    let found = messages(&report);
+    assert!(found
+        .iter()
+        .any(|m| m.starts_with("require(\"lib.d\") in module 'lib.c'")));
+    assert!(found.contains(&"__B_REQUIRE('main') names an unregistered module"));
+    assert!(found.contains(&"alias 'x' points to unregistered module 'nowhere'"));
+    let line = report
+        .issues
+        .iter()
+        .find(|i| i.message.contains("lib.d"))
+        .unwrap()
+        .line;
+    assert!(code.lines().nth(line - 1).unwrap().contains("require"));
+}
+
+#[test]
+fn invalid_syntax_is_reported() {
+    let code = bundle("graph_levels", &[]).replace("end\n\n-- root", "\n-- root");
+    assert!(!verify(&code, &[], &[]).issues.is_empty());
+    let report = verify("local x = 1\n", &[], &[]);
+    assert_eq!(
+        messages(&report),
+        ["no __B_MODULES registrations found; not a luapack bundle?"]
+    );
+}
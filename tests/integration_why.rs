@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use luapack::{
+    collect_vendor_modules, explain_module, format_explanation, parse_replace_rules,
+    parse_vendor_specs, Explanation, GraphExportCtx, ModuleKind, ModuleSource, NameNormalizer,
+    RuleOutcome, ROOT_MODULE,
+};
+
+mod common;
+
+fn why(module: &str, replace: &[&str]) -> Explanation {
+    let normalizer = NameNormalizer::new(HashSet::from(["init".to_string()]));
+    let (entry, _, resolver, graph) = common::build_graph(
+        "tests/fixtures/graph_export/lua/main.lua",
+        &["tests/fixtures/graph_export/lua/?.lua"],
+        &normalizer,
+    );
+    let flags: Vec<String> = replace.iter().map(|s| s.to_string()).collect();
+    let rules = parse_replace_rules(&flags).expect("parse rules");
+    let specs = parse_vendor_specs(&[common::manifest_path(
+        "tests/fixtures/graph_export/vendor/?.lua",
+    )])
+    .expect("parse vendor");
+    let (vendor_mods, _) = collect_vendor_modules(&specs, &rules, &normalizer).expect("vendor");
+    explain_module(
+        &graph,
+        &GraphExportCtx {
+            entry_path: &entry,
+            replaces: &rules,
+            vendor_mods: &vendor_mods,
+            externals: &[],
+            resolver: Some(&resolver),
+            normalizer: &normalizer,
+        },
+        &specs,
+        module,
+    )
+}
+
+#[test]
+fn why_reports_chain_template_and_suffix() {
+    let ex = why("app.log.init", &[]);
+    assert_eq!(ex.module, "app.log");
+    assert_eq!(ex.kind, Some(ModuleKind::FirstParty));
+    assert_eq!(
+        ex.chain.as_deref(),
+        Some(&[ROOT_MODULE.to_string(), "app.util".into(), "app.log".into()][..])
+    );
+    match &ex.source {
+        ModuleSource::Path { template, path } => {
+            assert!(template.ends_with("graph_export/lua/?.lua"));
+            assert!(path.ends_with("app/log.lua"));
+        }
+        other => panic!("unexpected source {:?}", other),
+    }
+    assert_eq!(
+        ex.stripped,
+        [("app.log.init".to_string(), vec!["init".to_string()])]
+    );
+    assert_eq!(ex.requires.len(), 1);
+    assert_eq!(ex.requires[0].from, "app.util");
+}
+
+#[test]
+fn why_reports_matching_and_failing_rules() {
+    let ex = why(
+        "svc.net",
+        &[
+            "match=exact,name=json,new=json_require",
+            "match=prefix,prefix=svc.,new=svc_require,arg={rest}",
+            "match=prefix,prefix=svc.,new=late_require",
+        ],
+    );
+    assert_eq!(ex.kind, Some(ModuleKind::Replaced));
+    assert!(matches!(ex.source, ModuleSource::NotFound));
+    let rules = &ex.requires[0].rules;
+    assert_eq!(
+        rules[0].outcome,
+        RuleOutcome::NoMatch("`svc.net` is not `json`".into())
+    );
+    assert_eq!(
+        rules[1].outcome,
+        RuleOutcome::Rewrites {
+            callee: "svc_require".into(),
+            arg: "net".into()
+        }
+    );
+    assert_eq!(rules[1].index, 1);
+    assert_eq!(rules[2].outcome, RuleOutcome::Shadowed);
+
+    let text = format_explanation(&ex);
+    assert!(
+        text.contains("rule #1 match=prefix,old=require,new=svc_require,prefix=svc.,arg={rest}: matches, rewritten to svc_require(\"net\")"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn why_explains_vendor_modules_left_out() {
+    let ex = why("json", &["match=exact,name=json,new=json_require"]);
+    match &ex.source {
+        ModuleSource::VendorSkipped { path, reason, .. } => {
+            assert!(path.ends_with("vendor/json.lua"));
+            assert_eq!(reason, "matched by a replace rule");
+        }
+        other => panic!("unexpected source {:?}", other),
+    }
+
+    let ex = why("json", &[]);
+    assert_eq!(ex.kind, Some(ModuleKind::Vendor));
+    assert!(matches!(ex.source, ModuleSource::Vendor { .. }));
+}
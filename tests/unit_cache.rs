@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use luapack::*;
+
+fn temp_cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("luapack-cache-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn first_party() -> ChunkKey {
+    ChunkKey {
+        transform: true,
+        resolved: String::new(),
+    }
+}
+
+fn chunk(code: &str) -> Option<CachedChunk> {
+    Some(CachedChunk {
+        code: code.into(),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn unchanged_files_hit_after_reload() {
+    let dir = temp_cache_dir("reload");
+    let file = Path::new("lua/a.lua");
+    let code = "local b = require('b')\n";
+
+    let cache = BuildCache::open(&dir, "fp1");
+    let found = cache.requires(file, code);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].module, "b");
+    let chunk = cache.chunk(file, code, &first_party(), || {
+        Some(CachedChunk {
+            code: "out".into(),
+            ..Default::default()
+        })
+    });
+    assert_eq!(chunk.map(|c| c.code).as_deref(), Some("out"));
+    assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    cache.save().expect("save");
+
+    let cache = BuildCache::open(&dir, "fp1");
+    assert_eq!(cache.requires(file, code)[0].module, "b");
+    let chunk = cache.chunk(file, code, &first_party(), || {
+        panic!("cached chunk must be reused")
+    });
+    assert_eq!(chunk.map(|c| c.code).as_deref(), Some("out"));
+    assert_eq!((cache.hits(), cache.misses()), (2, 0));
+
+    // Changed content misses again.
+    assert!(cache.requires(file, "return 1\n").is_empty());
+    assert_eq!(cache.misses(), 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn fingerprint_change_discards_entries() {
+    let dir = temp_cache_dir("fingerprint");
+    let file = Path::new("lua/a.lua");
+    let cache = BuildCache::open(&dir, "fp1");
+    cache.requires(file, "require('x')\n");
+    cache.save().expect("save");
+
+    let cache = BuildCache::open(&dir, "fp2");
+    cache.requires(file, "require('x')\n");
+    assert_eq!((cache.hits(), cache.misses()), (0, 1));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn failed_chunks_are_not_cached() {
+    let dir = temp_cache_dir("failed");
+    let file = Path::new("lua/a.lua");
+    let cache = BuildCache::open(&dir, "fp1");
+    assert!(cache.chunk(file, "x", &first_party(), || None).is_none());
+    assert!(cache.chunk(file, "x", &first_party(), || None).is_none());
+    assert_eq!(cache.misses(), 2);
+}
+
+#[test]
+fn transformed_and_plain_chunks_are_kept_apart() {
+    let dir = temp_cache_dir("kinds");
+    let file = Path::new("lua/a.lua");
+    let code = "return require('b')\n";
+    let plain = ChunkKey::default();
+    let cache = BuildCache::open(&dir, "fp1");
+    cache.chunk(file, code, &first_party(), || chunk("transformed"));
+    let found = cache.chunk(file, code, &plain, || chunk("plain"));
+    assert_eq!(found.map(|c| c.code).as_deref(), Some("plain"));
+    let found = cache.chunk(file, code, &first_party(), || panic!("must hit"));
+    assert_eq!(found.map(|c| c.code).as_deref(), Some("transformed"));
+    let found = cache.chunk(file, code, &plain, || panic!("must hit"));
+    assert_eq!(found.map(|c| c.code).as_deref(), Some("plain"));
+}
+
+#[test]
+fn changed_path_rule_inputs_miss() {
+    let dir = temp_cache_dir("resolved");
+    let file = Path::new("lua/a.lua");
+    let code = "return require('b')\n";
+    let key = |resolved: &str| ChunkKey {
+        transform: true,
+        resolved: resolved.to_string(),
+    };
+    let cache = BuildCache::open(&dir, "fp1");
+    cache.chunk(file, code, &key("b -> lua/b.lua\n"), || chunk("old"));
+    let found = cache.chunk(file, code, &key("b -> vendor/b/init.lua\n"), || {
+        chunk("new")
+    });
+    assert_eq!(found.map(|c| c.code).as_deref(), Some("new"));
+    assert_eq!((cache.hits(), cache.misses()), (0, 2));
+}
+
+#[test]
+fn in_memory_cache_is_kept_until_the_fingerprint_changes() {
+    let file = Path::new("lua/a.lua");
+    let code = "return require('b')\n";
+    let mut cache = BuildCache::in_memory();
+    cache.begin_build("fp1");
+    cache.chunk(file, code, &first_party(), || chunk("out"));
+    cache.save().expect("in-memory save is a no-op");
+
+    cache.begin_build("fp1");
+    let found = cache.chunk(file, code, &first_party(), || panic!("must hit"));
+    assert_eq!(found.map(|c| c.code).as_deref(), Some("out"));
+    assert_eq!((cache.hits(), cache.misses()), (1, 0));
+
+    cache.begin_build("fp2");
+    cache.chunk(file, code, &first_party(), || chunk("new"));
+    assert_eq!((cache.hits(), cache.misses()), (0, 1));
+}
@@ -0,0 +1,26 @@
+use std::path::{Path, PathBuf};
+
+use luapack::*;
+
+#[test]
+fn depfile_lists_unique_deps_in_order() {
+    let deps = vec![
+        PathBuf::from("lua/main.lua"),
+        PathBuf::from("lua/core/util.lua"),
+        PathBuf::from("lua/main.lua"),
+        PathBuf::from("luapack.toml"),
+    ];
+    let out = render_depfile(Path::new("dist/app.lua"), &deps);
+    assert_eq!(
+        out,
+        "dist/app.lua: \\\n  lua/main.lua \\\n  lua/core/util.lua \\\n  luapack.toml\n"
+    );
+}
+
+#[test]
+fn make_special_characters_escaped() {
+    assert_eq!(
+        escape_make_path(Path::new("my mods/#1/$x.lua")),
+        "my\\ mods/\\#1/$$x.lua"
+    );
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "lua54")]
+
+use luapack::*;
+
+#[test]
+fn integer_division_becomes_math_floor() {
+    let (out, n) = downlevel("local x = a // b\n", LuaDialect::Lua51).expect("downlevel");
+    assert_eq!(n, 1);
+    assert_eq!(out, "local x = math.floor(a / b)\n");
+}
+
+#[test]
+fn bitwise_ops_use_target_library() {
+    let code = "local x = a & (b | c) ~ ~d << 2\n";
+    let (jit, _) = downlevel(code, LuaDialect::LuaJit).expect("luajit");
+    assert_eq!(
+        jit,
+        "local x = bit.bxor(bit.band(a, (bit.bor(b, c))), bit.lshift(bit.bnot(d), 2))\n"
+    );
+    let (l52, _) = downlevel(code, LuaDialect::Lua52).expect("5.2");
+    assert!(l52.contains("bit32.band(a, (bit32.bor(b, c)))"), "{}", l52);
+    let (l53, n) = downlevel(code, LuaDialect::Lua53).expect("5.3");
+    assert_eq!(n, 0);
+    assert_eq!(l53, code);
+}
+
+#[test]
+fn const_attribute_dropped_and_close_rejected() {
+    let (out, _) = downlevel("local x <const> = 5\n", LuaDialect::Lua53).expect("const");
+    assert_eq!(out, "local x = 5\n");
+
+    let issues = downlevel("local f <close> = io.open('x')\n", LuaDialect::LuaJit).unwrap_err();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].line, 1);
+    assert!(issues[0].message.contains("<close>"), "{:?}", issues);
+}
+
+#[test]
+fn goto_rejected_only_on_lua51() {
+    let code = "for i = 1, 3 do\n  goto continue\n  ::continue::\nend\n";
+    let issues = downlevel(code, LuaDialect::Lua51).unwrap_err();
+    assert_eq!(issues.len(), 2, "{:?}", issues);
+    assert_eq!(issues[0].line, 2);
+    assert!(downlevel(code, LuaDialect::LuaJit).is_ok());
+}
@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use luapack::*;
+
+mod common;
+
+fn fixture_resolver(globs: &[&str]) -> ModuleResolver {
+    let base = common::manifest_path("tests/fixtures/exclude");
+    let globs: Vec<String> = globs.iter().map(|g| g.to_string()).collect();
+    let excludes = exclude_patterns(&globs, Some(Path::new(&base))).expect("globs");
+    ModuleResolver::new(vec![common::manifest_path(
+        "tests/fixtures/exclude/lua/?.lua",
+    )])
+    .with_excludes(excludes)
+}
+
+#[test]
+fn excluded_files_do_not_resolve() {
+    let resolver = fixture_resolver(&["lua/editor/**"]);
+    assert!(resolver.resolve("app").is_some());
+    assert!(resolver.resolve("editor.tools").is_none());
+    let (_, path) = resolver.excluded("editor.tools").expect("excluded");
+    assert!(path.ends_with("lua/editor/tools.lua"));
+    assert!(resolver.excluded("app").is_none());
+    assert!(resolver.excluded("missing").is_none());
+}
+
+#[test]
+fn globs_match_anywhere_below_the_base() {
+    let resolver = fixture_resolver(&["**/test_*.lua"]);
+    assert!(resolver.resolve("test_helper").is_none());
+    assert!(resolver.resolve("editor.tools").is_some());
+}
+
+#[test]
+fn ignore_file_skips_blank_lines_and_comments() {
+    let globs = read_ignore_file(Path::new(&common::manifest_path("tests/fixtures/exclude")))
+        .expect("read");
+    assert_eq!(globs, ["lua/editor/**"]);
+    let none = read_ignore_file(Path::new(&common::manifest_path("tests/fixtures/include")))
+        .expect("missing file");
+    assert!(none.is_empty());
+}
+
+#[test]
+fn ignore_comment_hides_a_require_from_the_scan() {
+    let code = "local a = require('a') -- luapack: ignore\nlocal b = require('b')\nlocal c = require(\n  'c' -- luapack: ignore\n)\n";
+    let found: Vec<String> = find_literal_requires(code)
+        .into_iter()
+        .map(|r| r.module)
+        .collect();
+    assert_eq!(found, ["b"]);
+
+    let dynamic = find_dynamic_requires("local m = require(prefix .. name) -- luapack: ignore\n");
+    assert!(dynamic.is_empty());
+}
+
+#[test]
+fn ignore_comment_keeps_a_require_as_written() {
+    let rules =
+        parse_replace_rules(&["match=prefix,old=require,new=lib_require,prefix=lib.".to_string()])
+            .unwrap();
+    let code = "local a = require('lib.a') -- luapack: ignore\nlocal b = require('lib.b')\n";
+    let (out, rewrites) = transform_requires(
+        code,
+        &rules,
+        None,
+        None,
+        &NameNormalizer::new(HashSet::new()),
+    );
+    assert_eq!(rewrites, 1);
+    assert_eq!(
+        out,
+        "local a = require('lib.a') -- luapack: ignore\nlocal b = lib_require('lib.b')\n"
+    );
+}
@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use luapack::{expand_includes, find_dynamic_requires, find_literal_requires, NameNormalizer};
+
+mod common;
+
+fn included(globs: &[&str]) -> anyhow::Result<Vec<String>> {
+    let templates = vec![common::manifest_path("tests/fixtures/include/lua/?.lua")];
+    let globs: Vec<String> = globs.iter().map(|g| g.to_string()).collect();
+    let normalizer = NameNormalizer::new(HashSet::new());
+    Ok(expand_includes(&globs, &templates, &normalizer)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+#[test]
+fn star_matches_one_name_segment() {
+    assert_eq!(
+        included(&["widgets.*"]).unwrap(),
+        ["widgets.button", "widgets.label"]
+    );
+}
+
+#[test]
+fn double_star_matches_any_depth() {
+    assert_eq!(
+        included(&["themes.**", "widgets.form.*"]).unwrap(),
+        ["themes.dark", "themes.extra.neon", "widgets.form.input"]
+    );
+}
+
+#[test]
+fn glob_matching_nothing_is_an_error() {
+    let err = included(&["widget.*"]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("--include widget.* matches no module"),
+        "{err}"
+    );
+}
+
+#[test]
+fn dynamic_requires_are_listed_with_positions() {
+    let code = "local a = require('a')\nlocal m = require(\"w.\" .. name)\nrequire(prefix, 'x')\n";
+    let found = find_dynamic_requires(code);
+    let listed: Vec<(&str, usize, usize)> = found
+        .iter()
+        .map(|d| (d.argument.as_str(), d.line, d.col))
+        .collect();
+    assert_eq!(listed, [("\"w.\" .. name", 2, 11), ("prefix", 3, 1)]);
+}
+
+#[test]
+fn literal_requires_carry_positions() {
+    let found = find_literal_requires("local a = require('a')\n  require 'b'\n");
+    let listed: Vec<(&str, usize, usize)> = found
+        .iter()
+        .map(|r| (r.module.as_str(), r.line, r.col))
+        .collect();
+    assert_eq!(listed, [("a", 1, 11), ("b", 2, 3)]);
+}
@@ -0,0 +1,49 @@
+use luapack::{scan_globals, stdlib_globals, LuaDialect};
+
+fn names(uses: &[(String, (usize, usize))]) -> Vec<&str> {
+    uses.iter().map(|(n, _)| n.as_str()).collect()
+}
+
+#[test]
+fn locals_parameters_and_loop_variables_are_not_globals() {
+    let code = r#"
+local a = b
+local function f(x, ...)
+  for i, v in pairs(x) do print(i, v, a) end
+  for n = 1, limit do y = n end
+  return f
+end
+function obj:method() return self end
+"#;
+    let uses = scan_globals(code).unwrap();
+    assert_eq!(names(&uses.reads), ["b", "pairs", "print", "limit", "obj"]);
+    assert_eq!(names(&uses.writes), ["y"]);
+}
+
+#[test]
+fn local_is_in_scope_only_after_its_declaration() {
+    let uses = scan_globals("local x = x\ndo local y = 1 end\nreturn y").unwrap();
+    assert_eq!(names(&uses.reads), ["x", "y"]);
+    assert_eq!(uses.reads[1].1, (3, 8));
+}
+
+#[test]
+fn repeat_until_sees_body_locals() {
+    let uses = scan_globals("repeat local done = true until done").unwrap();
+    assert!(uses.reads.is_empty());
+}
+
+#[test]
+fn field_assignments_and_declarations_read_the_table() {
+    let uses = scan_globals("t.x = 1\nfunction M.f() end\nfunction g() end").unwrap();
+    assert_eq!(names(&uses.reads), ["t", "M"]);
+    assert_eq!(names(&uses.writes), ["g"]);
+}
+
+#[test]
+fn stdlib_follows_the_dialect() {
+    assert!(stdlib_globals(LuaDialect::Lua51).contains(&"setfenv"));
+    assert!(!stdlib_globals(LuaDialect::Lua54).contains(&"setfenv"));
+    assert!(stdlib_globals(LuaDialect::Lua54).contains(&"warn"));
+    assert!(stdlib_globals(LuaDialect::LuaJit).contains(&"jit"));
+}
@@ -54,3 +54,22 @@ fn parse_replace_rules_invalid_match_kind() {
     let err = parse_replace_rules(&flags).unwrap_err();
     assert!(format!("{err}").contains("unknown match kind"));
 }
+
+#[test]
+fn replace_rule_displays_in_flag_syntax() {
+    let flags = vec![
+        "match=prefix,prefix=bar.,new=bar_require,arg={rest}".to_string(),
+        "match=path,new=v_require,path=vendor/**/init.lua,path=lib/*.lua".to_string(),
+    ];
+    let rules = parse_replace_rules(&flags).expect("parse");
+    let shown: Vec<String> = rules.iter().map(|r| r.to_string()).collect();
+    assert_eq!(
+        shown,
+        [
+            "match=prefix,old=require,new=bar_require,prefix=bar.,arg={rest}",
+            "match=path,old=require,new=v_require,path=vendor/**/init.lua,path=lib/*.lua,arg={full}",
+        ]
+    );
+    let reparsed = parse_replace_rules(&shown).expect("reparse");
+    assert_eq!(reparsed[1].paths, rules[1].paths);
+}
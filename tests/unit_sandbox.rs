@@ -0,0 +1,26 @@
+use luapack::{parse_sandbox_allow, SandboxAllow};
+
+#[test]
+fn parses_allow_rules() {
+    let rules =
+        parse_sandbox_allow(&["widgets.=WG, widget".to_string(), "*=Spring".to_string()]).unwrap();
+    assert_eq!(
+        rules,
+        [
+            SandboxAllow {
+                prefix: "widgets.".to_string(),
+                globals: vec!["WG".to_string(), "widget".to_string()],
+            },
+            SandboxAllow {
+                prefix: String::new(),
+                globals: vec!["Spring".to_string()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn rejects_malformed_allow_rules() {
+    assert!(parse_sandbox_allow(&["widgets.".to_string()]).is_err());
+    assert!(parse_sandbox_allow(&["widgets.=".to_string()]).is_err());
+}
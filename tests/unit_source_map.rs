@@ -0,0 +1,62 @@
+use std::fs;
+use std::path::PathBuf;
+
+use luapack::{ChunkSpan, SourceMap};
+
+mod common;
+
+#[test]
+fn spans_cover_original_lines() {
+    let (bundle, stats) = common::bundle_with_stats_for(
+        "tests/fixtures/run/lua/main.lua",
+        &["tests/fixtures/run/lua/?.lua"],
+        common::BundleOptions {
+            entry_override: None,
+            preludes: &[PathBuf::from(common::manifest_path(
+                "tests/fixtures/run/stubs/host.lua",
+            ))],
+            replaces: &[],
+            vendor_specs: &[],
+        },
+    );
+    let modules: Vec<Option<&str>> = stats
+        .source_map
+        .chunks
+        .iter()
+        .map(|c| c.module.as_deref())
+        .collect();
+    assert_eq!(modules, [Some("app.fail"), Some("__root"), None]);
+
+    let lines: Vec<&str> = bundle.lines().collect();
+    for span in &stats.source_map.chunks {
+        let source = fs::read_to_string(&span.path).unwrap();
+        let original: Vec<&str> = source.lines().collect();
+        assert_eq!(span.line_count, original.len());
+        assert_eq!(
+            lines[span.first_line - 1..span.first_line - 1 + span.line_count],
+            original[..],
+            "{}",
+            span.path.display()
+        );
+    }
+}
+
+#[test]
+fn rewrite_maps_bundle_positions() {
+    let map = SourceMap {
+        chunks: vec![ChunkSpan {
+            module: Some("app.fail".to_string()),
+            path: PathBuf::from("lua/app/fail.lua"),
+            first_line: 20,
+            line_count: 8,
+        }],
+    };
+    let text = "luapack-bundle:24: failed\nstack traceback:\n\tluapack-bundle:24: in function <luapack-bundle:23>\n\tluapack-bundle:3: in ?";
+    assert_eq!(
+        map.rewrite("luapack-bundle", text),
+        "lua/app/fail.lua:5: failed\nstack traceback:\n\tlua/app/fail.lua:5: in function <lua/app/fail.lua:4>\n\tluapack-bundle:3: in ?"
+    );
+    assert_eq!(map.locate(19), None);
+    assert_eq!(map.locate(27).map(|(_, l)| l), Some(8));
+    assert_eq!(map.locate(28), None);
+}
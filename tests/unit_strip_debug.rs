@@ -0,0 +1,80 @@
+use luapack::strip_debug;
+
+#[test]
+fn strips_assert_statements_and_keeps_lines() {
+    let code = "local x = f()\nassert(x,\n  'no x')\nassert \"msg\"; print(x) -- done\nlocal ok = assert(io.open('f'))\nassert(g)()\n";
+    let (out, n) = strip_debug(code, true, false).unwrap();
+    assert_eq!(n, 2);
+    assert_eq!(
+        out,
+        "local x = f()\n\n\n print(x) -- done\nlocal ok = assert(io.open('f'))\nassert(g)()\n"
+    );
+}
+
+#[test]
+fn strips_nested_asserts_in_blocks() {
+    let code = "if x then\n  assert(x > 0)\nend\nreturn function() assert(y) end\n";
+    let (out, n) = strip_debug(code, true, false).unwrap();
+    assert_eq!(n, 2);
+    assert_eq!(out, "if x then\n  \nend\nreturn function()  end\n");
+}
+
+#[test]
+fn keeps_calls_from_merging_with_the_previous_statement() {
+    let (out, _) = strip_debug("local a = b\nassert(a);\n(f)()\n", true, false).unwrap();
+    assert_eq!(out, "local a = b\n;\n(f)()\n");
+}
+
+#[test]
+fn adds_no_semicolon_after_one_already_there() {
+    // Lua 5.1 rejects `;;`, so the kept statement's own `;` has to do.
+    let (out, _) = strip_debug("local t = {};\nassert(t);\n(f)(\"hi\")\n", true, false).unwrap();
+    assert_eq!(out, "local t = {};\n\n(f)(\"hi\")\n");
+    let (out, _) =
+        strip_debug("local t = {}\nassert(t)\nassert(t);\n(f)()\n", true, false).unwrap();
+    assert_eq!(out, "local t = {}\n\n;\n(f)()\n");
+}
+
+#[test]
+fn strips_marked_regions() {
+    let code = "local a = 1\n--@debug begin\nprint('debug', a)\n-- @debug begin\nassert(a)\n--@debug end\n--@debug end\nreturn a\n";
+    let (out, n) = strip_debug(code, true, true).unwrap();
+    assert_eq!(n, 1);
+    assert_eq!(out, "local a = 1\n\n\n\n\n\n\nreturn a\n");
+}
+
+#[test]
+fn markers_are_left_alone_unless_asked() {
+    let code = "--@debug begin\nprint(1)\n--@debug end\nassert(true)\n";
+    let (out, n) = strip_debug(code, false, false).unwrap();
+    assert_eq!((out.as_str(), n), (code, 0));
+    let (out, n) = strip_debug(code, true, false).unwrap();
+    assert_eq!(
+        (out.as_str(), n),
+        ("--@debug begin\nprint(1)\n--@debug end\n\n", 1)
+    );
+}
+
+#[test]
+fn rejects_unbalanced_or_partial_regions() {
+    let err = strip_debug("--@debug begin\nprint(1)\n", false, true).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 1: --@debug begin without --@debug end"
+    );
+    let err = strip_debug("print(1)\n--@debug end\n", false, true).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 2: --@debug end without --@debug begin"
+    );
+    let err = strip_debug(
+        "local t = {\n--@debug begin\n}\n--@debug end\n",
+        false,
+        true,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "removing --@debug regions leaves invalid Lua; does a region cut a statement in two?"
+    );
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "luau")]
+
+use luapack::*;
+
+#[test]
+fn annotations_and_casts_removed() {
+    let code = "local x: number = 1\nlocal function add<T>(a: T, b: T): T\n  return (a :: any) + b\nend\nfor i: number = 1, 2 do end\nfor k: string, v in pairs(t) do end\n";
+    let (out, n) = strip_types(code);
+    assert_eq!(
+        out,
+        "local x = 1\nlocal function add(a, b)\n  return (a) + b\nend\nfor i = 1, 2 do end\nfor k, v in pairs(t) do end\n"
+    );
+    assert_eq!(n, 8);
+    assert!(validate_dialect(&out, LuaDialect::Lua51).is_empty());
+}
+
+#[test]
+fn type_declarations_keep_line_numbers() {
+    let code = "export type Point = {\n  x: number,\n  y: number,\n}\nlocal p = {x = 1, y = 2}\ntype Id = string\nreturn p\n";
+    let (out, n) = strip_types(code);
+    assert_eq!(n, 2);
+    assert_eq!(out, "\n\n\n\nlocal p = {x = 1, y = 2}\n\nreturn p\n");
+    assert_eq!(out.lines().count(), code.lines().count());
+}
+
+#[test]
+fn plain_lua_unchanged() {
+    let code = "local t = {}\n::done::\nreturn t\n";
+    assert_eq!(strip_types(code), (code.to_string(), 0));
+}
+
+#[test]
+fn luau_syntax_reported_for_lua_targets() {
+    let code = "local x: number = 1\nx += 1\nlocal s = `x={x}`\n";
+    let issues = validate_dialect(code, LuaDialect::Lua51);
+    assert_eq!(issues.len(), 3, "{:?}", issues);
+    assert_eq!(issues[0].line, 1);
+    assert!(
+        issues[0].message.contains("type annotation"),
+        "{:?}",
+        issues
+    );
+    assert!(
+        issues[1].message.contains("compound assignment"),
+        "{:?}",
+        issues
+    );
+    assert!(issues[2].message.contains("interpolation"), "{:?}", issues);
+    assert!(validate_dialect(code, LuaDialect::Luau).is_empty());
+}
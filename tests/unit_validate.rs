@@ -0,0 +1,40 @@
+#![cfg(feature = "lua54")]
+
+use luapack::*;
+
+#[test]
+fn reports_lua54_constructs_for_lua51() {
+    let code = "local x <const> = 1\nlocal y = x // 2 | 1\n";
+    let issues = validate_dialect(code, LuaDialect::Lua51);
+    let found: Vec<(usize, usize)> = issues.iter().map(|i| (i.line, i.col)).collect();
+    assert_eq!(found, vec![(1, 9), (2, 13), (2, 18)], "{:?}", issues);
+    assert!(issues[1].message.contains("integer division"));
+    assert!(validate_dialect(code, LuaDialect::Lua54).is_empty());
+}
+
+#[test]
+fn goto_ok_for_luajit_and_52_but_not_51() {
+    let code = "goto done\n::done::\n";
+    assert_eq!(validate_dialect(code, LuaDialect::Lua51).len(), 2);
+    assert!(validate_dialect(code, LuaDialect::Lua52).is_empty());
+    assert!(validate_dialect(code, LuaDialect::LuaJit).is_empty());
+}
+
+#[test]
+fn luajit_literals_rejected_elsewhere() {
+    let code = "local a = 10ULL\nlocal b = 0b101\n";
+    assert!(validate_dialect(code, LuaDialect::LuaJit).is_empty());
+    let issues = validate_dialect(code, LuaDialect::Lua54);
+    assert_eq!(issues.len(), 2, "{:?}", issues);
+    assert!(issues[0].message.contains("10ULL"));
+}
+
+#[test]
+fn issues_render_with_file_location() {
+    let issues = validate_dialect("local x = 1 // 2\n", LuaDialect::LuaJit);
+    let text = format_issues(std::path::Path::new("lua/a.lua"), &issues);
+    assert_eq!(
+        text,
+        "lua/a.lua:1:13: integer division `//` is not supported by Lua luajit"
+    );
+}
@@ -60,3 +60,99 @@ fn to_glob_and_root_cases() {
     assert_eq!(root2.to_string_lossy(), "a/b/");
     assert!(init2);
 }
+
+fn filtered_vendor(keys: &str) -> Vec<String> {
+    let flag = format!(
+        "path={},{}",
+        common::manifest_path("tests/fixtures/vendor_filter/vendor/lua/?.lua"),
+        keys
+    );
+    let specs = parse_vendor_specs(&[flag]).expect("parse vendor specs");
+    let (mods, _) =
+        collect_vendor_modules(&specs, &[], &NameNormalizer::default()).expect("collect vendor");
+    let mut names: Vec<String> = mods.into_keys().collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn vendor_exclude_globs_match_names_and_paths() {
+    assert_eq!(
+        filtered_vendor("exclude=glob:**/spec/**,exclude=glob:**/test_*.lua"),
+        ["examples.demo", "inspect", "json", "lume"]
+    );
+    assert_eq!(
+        filtered_vendor("exclude=glob:examples.*"),
+        [
+            "inspect",
+            "json",
+            "json.spec.json_spec",
+            "lume",
+            "test_utils"
+        ]
+    );
+}
+
+#[test]
+fn vendor_exclude_regex() {
+    assert_eq!(
+        filtered_vendor("exclude=regex:(_spec|^test_)"),
+        ["examples.demo", "inspect", "json", "lume"]
+    );
+    assert_eq!(
+        filtered_vendor("exclude=regex:/examples/"),
+        [
+            "inspect",
+            "json",
+            "json.spec.json_spec",
+            "lume",
+            "test_utils"
+        ]
+    );
+}
+
+#[test]
+fn vendor_include_allowlist() {
+    assert_eq!(
+        filtered_vendor("include=name:json,include=name:lume"),
+        ["json", "lume"]
+    );
+    // Exclusions still apply to allowed modules.
+    assert_eq!(
+        filtered_vendor("include=glob:json*,exclude=glob:**/spec/**"),
+        ["json"]
+    );
+}
+
+#[test]
+fn vendor_filters_report_bad_patterns() {
+    let err = parse_vendor_specs(&["path=v/?.lua,exclude=regex:(".to_string()]).unwrap_err();
+    assert!(err.to_string().contains("bad exclude=regex:("), "{err}");
+    let err = parse_vendor_specs(&["path=v/?.lua,include=glob:[".to_string()]).unwrap_err();
+    assert!(err.to_string().contains("bad include=glob:["), "{err}");
+}
+
+#[test]
+fn vendor_rejects_names_the_filter() {
+    let specs = parse_vendor_specs(&[
+        "path=v/?.lua,include=name:json,exclude=glob:**/spec/**,exclude=regex:^test_".to_string(),
+    ])
+    .expect("parse");
+    let spec = &specs[0];
+    let path = std::path::Path::new;
+    assert_eq!(spec.rejects("json", path("v/json.lua")), None);
+    assert_eq!(
+        spec.rejects("json.spec.a", path("v/json/spec/a.lua"))
+            .as_deref(),
+        Some("excluded by exclude=glob:**/spec/**")
+    );
+    assert_eq!(
+        spec.rejects("test_utils", path("v/test_utils.lua"))
+            .as_deref(),
+        Some("excluded by exclude=regex:^test_")
+    );
+    assert_eq!(
+        spec.rejects("lume", path("v/lume.lua")).as_deref(),
+        Some("not matched by any include=")
+    );
+}